@@ -0,0 +1,47 @@
+use crate::PasteError;
+use std::path::Path;
+
+/// How deep `archive_dir` goes below the directory unless told otherwise.
+pub const DEFAULT_ARCHIVE_DEPTH: usize = 16;
+
+/// Packs the files under `dir` into a gzipped tar, with paths starting at the directory's own
+/// name. Files matched by `.gitignore` and similar files are left out when `respect_ignores` is
+/// set, and `.git` itself always is.
+pub fn archive_dir(dir: &Path, max_depth: usize, respect_ignores: bool) -> Result<Vec<u8>, PasteError> {
+    let root = dir_name(dir);
+    let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    let mut tar = tar::Builder::new(encoder);
+    tar.follow_symlinks(false);
+
+    let walk = ignore::WalkBuilder::new(dir)
+        .standard_filters(respect_ignores)
+        .hidden(false)
+        .require_git(false)
+        .max_depth(Some(max_depth))
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+
+    let mut files = 0;
+    for entry in walk {
+        let entry = entry.map_err(|e| std::io::Error::other(e.to_string()))?;
+        let relative = entry.path().strip_prefix(dir).map_err(std::io::Error::other)?;
+        let name = match relative.as_os_str().is_empty() {
+            true => Path::new(&root).to_path_buf(),
+            false => Path::new(&root).join(relative),
+        };
+        tracing::trace!("archiving {}", name.display());
+        tar.append_path_with_name(entry.path(), &name)?;
+        if entry.file_type().is_some_and(|kind| kind.is_file()) {
+            files += 1;
+        }
+    }
+    tracing::debug!("archived {} files from {}", files, dir.display());
+
+    Ok(tar.into_inner()?.finish()?)
+}
+
+// Paths like `.` only have a name once they're resolved
+pub(crate) fn dir_name(dir: &Path) -> String {
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    dir.file_name().and_then(|name| name.to_str()).unwrap_or("archive").to_string()
+}
@@ -0,0 +1,95 @@
+use super::{PasteBackend, UploadOptions, Uploaded};
+use crate::{ExpireSpec, PasteError};
+use reqwest::Url;
+
+pub const DPASTE_URL: &str = "https://dpaste.com/";
+
+const DAY: u64 = 86400;
+// dpaste.com keeps pastes for at least a day and at most a year
+const MAX_EXPIRY_DAYS: u64 = 365;
+
+/// dpaste.com, through its `api/v2` endpoint. dpaste.org runs the dpaste software that
+/// paste.mozilla.org does, so it's reached with the mozilla backend and its URL instead.
+pub struct DpasteBackend {
+    client: reqwest::Client,
+    base_url: Url,
+}
+
+impl DpasteBackend {
+    pub fn new(client: reqwest::Client) -> Self {
+        DpasteBackend { client, base_url: DPASTE_URL.parse().unwrap() }
+    }
+
+    pub fn with_base_url(client: reqwest::Client, base_url: Url) -> Result<Self, PasteError> {
+        Ok(DpasteBackend { client, base_url: super::base_url(base_url)? })
+    }
+}
+
+#[async_trait::async_trait]
+impl PasteBackend for DpasteBackend {
+    async fn upload(&self, content: &str, opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        let fields = self.fields(opts);
+        tracing::debug!("form fields {:?}", fields);
+        let api = self.base_url.join("api/v2/").map_err(|e| PasteError::InvalidResponse(e.to_string()))?;
+        let mut form = vec![("content", content.to_string())];
+        form.extend(fields);
+        let res = self.client.post(api).form(&form).send().await?;
+        tracing::debug!("dpaste.com returned {}", res.status());
+
+        // Unknown syntax names and the like are explained in the body
+        super::rate_limited(&res)?;
+        let status = res.status();
+        if status.is_client_error() {
+            let reason = res.text().await.unwrap_or_default().trim().to_string();
+            return Err(PasteError::UploadRejected { status, reason });
+        }
+        let res = super::check_status(res)?;
+
+        let location = res.headers().get(reqwest::header::LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .ok_or_else(|| PasteError::InvalidResponse("no Location header in the response".to_string()))?;
+        let url = self.base_url.join(location).map_err(|_| PasteError::InvalidResponse(location.to_string()))?;
+        Ok(Uploaded { expires: Some(ExpireSpec::Seconds(rounded_days(opts.expire) * DAY)), ..url.into() })
+    }
+
+    // The raw text is served next to the paste with a `.txt` extension
+    async fn fetch(&self, url: &Url) -> Result<Vec<u8>, PasteError> {
+        let mut raw = url.clone();
+        let path = url.path().trim_end_matches('/');
+        raw.set_path(&format!("{}.txt", path.strip_suffix(".txt").unwrap_or(path)));
+        raw.set_fragment(None);
+        let res = super::get_raw(&self.client, raw, url).await?;
+        Ok(res.bytes().await?.to_vec())
+    }
+
+    fn fields(&self, opts: &UploadOptions) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("syntax", self.translate_lexer(&opts.lang).unwrap_or_else(|| "text".to_string())),
+            ("expiry_days", expiry_days(opts.expire).to_string()),
+        ];
+        if !opts.title.is_empty() {
+            fields.push(("title", opts.title.clone()));
+        }
+        fields
+    }
+
+    // dpaste.com highlights with Pygments
+    fn translate_lexer(&self, canonical: &str) -> Option<String> {
+        super::lexer::translate(super::lexer::PYGMENTS, canonical)
+    }
+}
+
+fn expiry_days(spec: ExpireSpec) -> u64 {
+    let days = rounded_days(spec);
+    match spec.seconds() {
+        None => tracing::warn!("dpaste.com has no one-time pastes, keeping it for a day instead"),
+        Some(secs) if days * DAY != secs => tracing::warn!("dpaste.com only keeps pastes for whole days, from 1 to {}; rounding {} to {} days", MAX_EXPIRY_DAYS, spec, days),
+        Some(_) => {}
+    }
+    days
+}
+
+// Expiry is counted in whole days, so lifetimes are rounded up to the next one
+fn rounded_days(spec: ExpireSpec) -> u64 {
+    spec.seconds().map_or(1, |secs| secs.div_ceil(DAY).clamp(1, MAX_EXPIRY_DAYS))
+}
@@ -0,0 +1,78 @@
+use super::{PasteBackend, UploadOptions, Uploaded};
+use crate::PasteError;
+use reqwest::Url;
+
+/// Tries each of several backends in turn until one of them creates the paste. Downloads,
+/// deletions and the fields shown for a dry run are left to the first one.
+pub struct Fallback {
+    backends: Vec<(String, Box<dyn PasteBackend>)>,
+}
+
+impl Fallback {
+    /// Takes the backends in the order to try them, each with the name to report it by.
+    pub fn new(backends: Vec<(String, Box<dyn PasteBackend>)>) -> Self {
+        assert!(!backends.is_empty(), "Fallback needs at least one backend");
+        Fallback { backends }
+    }
+
+    fn primary(&self) -> &dyn PasteBackend {
+        self.backends[0].1.as_ref()
+    }
+}
+
+#[async_trait::async_trait]
+impl PasteBackend for Fallback {
+    async fn upload(&self, content: &str, opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        self.upload_bytes(content.as_bytes(), opts).await
+    }
+
+    // The first error is the one reported, since the first backend is the one asked for
+    async fn upload_bytes(&self, content: &[u8], opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        let mut first_error = None;
+        for (name, backend) in &self.backends {
+            // Text-only backends would refuse it anyway
+            if std::str::from_utf8(content).is_err() && !backend.supports_binary() {
+                tracing::debug!("skipping {}, which only takes text", name);
+                continue;
+            }
+            match backend.upload_bytes(content, opts).await {
+                Ok(mut uploaded) => {
+                    tracing::info!("uploaded to {}", name);
+                    uploaded.backend = Some(name.clone());
+                    return Ok(uploaded);
+                }
+                Err(e) => {
+                    tracing::debug!("{} failed: {}", name, e);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        Err(first_error.unwrap_or(PasteError::NotUtf8))
+    }
+
+    async fn delete(&self, url: &Url, token: Option<&str>) -> Result<(), PasteError> {
+        self.primary().delete(url, token).await
+    }
+
+    async fn fetch(&self, url: &Url) -> Result<Vec<u8>, PasteError> {
+        self.primary().fetch(url).await
+    }
+
+    fn fields(&self, opts: &UploadOptions) -> Vec<(&'static str, String)> {
+        self.primary().fields(opts)
+    }
+
+    // A stream can only be sent once, so the content has to be at hand to try again
+    fn supports_streaming(&self) -> bool {
+        false
+    }
+
+    fn supports_binary(&self) -> bool {
+        self.backends.iter().any(|(_, backend)| backend.supports_binary())
+    }
+
+    // Each backend translates for itself when it gets the upload; this is the first one's name
+    fn translate_lexer(&self, canonical: &str) -> Option<String> {
+        self.backends.first().and_then(|(_, backend)| backend.translate_lexer(canonical))
+    }
+}
@@ -0,0 +1,121 @@
+use super::{PasteBackend, UploadOptions, Uploaded};
+use crate::{ExpireSpec, PasteError};
+use reqwest::Url;
+
+const API_URL: &str = "https://api.github.com/gists";
+
+/// GitHub Gist, authenticated with a personal access token.
+pub struct GistBackend {
+    client: reqwest::Client,
+    token: String,
+    public: bool,
+}
+
+#[derive(serde::Deserialize)]
+struct GistResponse {
+    html_url: String,
+    #[serde(default)]
+    files: std::collections::BTreeMap<String, GistFile>,
+}
+
+#[derive(serde::Deserialize)]
+struct GistFile {
+    content: String,
+}
+
+impl GistBackend {
+    pub fn new(client: reqwest::Client, token: String, public: bool) -> Self {
+        GistBackend { client, token, public }
+    }
+
+    /// Reads the token from `$GITHUB_TOKEN`.
+    pub fn from_env(client: reqwest::Client, public: bool) -> Result<Self, PasteError> {
+        let token = std::env::var("GITHUB_TOKEN").ok()
+            .filter(|token| !token.is_empty())
+            .ok_or(PasteError::MissingEnv("GITHUB_TOKEN"))?;
+        Ok(GistBackend::new(client, token, public))
+    }
+}
+
+#[async_trait::async_trait]
+impl PasteBackend for GistBackend {
+    async fn upload(&self, content: &str, opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        if opts.expire != ExpireSpec::Once {
+            tracing::warn!("gists don't expire, ignoring expire time {}", opts.expire);
+        }
+
+        let filename = gist_filename(opts);
+        let body = serde_json::json!({
+            "description": opts.title,
+            "public": self.public,
+            "files": { filename: { "content": content } },
+        });
+
+        tracing::debug!("creating gist {:?} with {} bytes", filename, content.len());
+        let res = self.client.post(API_URL)
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .json(&body)
+            .send()
+            .await?;
+        tracing::debug!("GitHub returned {}", res.status());
+        let res = super::check_status(res)?;
+
+        let gist: GistResponse = res.json().await?;
+        let url = Url::parse(&gist.html_url).map_err(|_| PasteError::InvalidResponse(gist.html_url))?;
+        Ok(url.into())
+    }
+
+    // Gists made here hold a single file, so that's the content; others get all files in name order
+    async fn fetch(&self, url: &Url) -> Result<Vec<u8>, PasteError> {
+        let id = gist_id(url)?;
+        let api = Url::parse(&format!("{}/{}", API_URL, id)).map_err(|e| PasteError::InvalidResponse(e.to_string()))?;
+        tracing::debug!("fetching gist {}", id);
+        let res = self.client.get(api)
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .send()
+            .await?;
+        tracing::debug!("GitHub returned {}", res.status());
+        if res.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(PasteError::NotFound(url.clone()));
+        }
+
+        let gist: GistResponse = super::check_status(res)?.json().await?;
+        let content: Vec<String> = gist.files.into_values().map(|file| file.content).collect();
+        Ok(content.join("\n").into_bytes())
+    }
+
+    // Gists belong to the token's account, so no separate deletion token is needed
+    async fn delete(&self, url: &Url, _token: Option<&str>) -> Result<(), PasteError> {
+        let id = gist_id(url)?;
+        tracing::debug!("deleting gist {}", id);
+        let res = self.client.delete(format!("{}/{}", API_URL, id))
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .send()
+            .await?;
+        tracing::debug!("GitHub returned {}", res.status());
+        super::check_status(res)?;
+        Ok(())
+    }
+
+    fn fields(&self, opts: &UploadOptions) -> Vec<(&'static str, String)> {
+        vec![
+            ("description", opts.title.clone()),
+            ("public", self.public.to_string()),
+            ("filename", gist_filename(opts).to_string()),
+        ]
+    }
+}
+
+fn gist_id(url: &Url) -> Result<&str, PasteError> {
+    url.path_segments().and_then(|mut segments| segments.next_back())
+        .filter(|id| !id.is_empty())
+        .ok_or_else(|| PasteError::InvalidResponse(format!("{} is not a gist URL", url)))
+}
+
+// Gist highlights based on the file name, so keep the original one when there is one
+fn gist_filename(opts: &UploadOptions) -> &str {
+    opts.filename.as_deref().unwrap_or("paste.txt")
+}
@@ -0,0 +1,77 @@
+use super::{PasteBackend, UploadOptions, Uploaded};
+use crate::{ExpireSpec, PasteError};
+use reqwest::Url;
+
+pub const DEFAULT_HASTE_HOST: &str = "https://hastebin.com";
+
+/// A haste-server instance, hastebin.com unless pointed at a self-hosted one.
+pub struct HasteBackend {
+    client: reqwest::Client,
+    host: Url,
+}
+
+#[derive(serde::Deserialize)]
+struct HasteResponse {
+    key: String,
+}
+
+impl HasteBackend {
+    pub fn new(client: reqwest::Client, host: Url) -> Result<Self, PasteError> {
+        Ok(HasteBackend { client, host: super::base_url(host)? })
+    }
+
+    async fn submit(&self, body: reqwest::Body, len: Option<u64>, opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        if opts.expire != ExpireSpec::Once || opts.lang != "_code" {
+            tracing::info!("haste-server has no expire time or lexer, ignoring them");
+        }
+
+        let documents = self.host.join("documents").map_err(|e| PasteError::InvalidResponse(e.to_string()))?;
+        let mut request = self.client.post(documents.clone()).header("Content-Type", "text/plain; charset=utf-8");
+        // Without a length the body goes out in chunks
+        match len {
+            Some(len) => {
+                tracing::debug!("posting {} bytes to {}", len, documents);
+                request = request.header("Content-Length", len);
+            }
+            None => tracing::debug!("posting piped input to {}", documents),
+        }
+        let res = request.body(body).send().await?;
+        tracing::debug!("haste-server returned {}", res.status());
+        let res = super::check_status(res)?;
+
+        let document: HasteResponse = res.json().await?;
+        let url = self.host.join(&document.key).map_err(|_| PasteError::InvalidResponse(document.key))?;
+        Ok(url.into())
+    }
+}
+
+#[async_trait::async_trait]
+impl PasteBackend for HasteBackend {
+    async fn upload(&self, content: &str, opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        self.submit(content.to_string().into(), Some(content.len() as u64), opts).await
+    }
+
+    async fn upload_stream(&self, body: reqwest::Body, len: Option<u64>, opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        self.submit(body, len, opts).await
+    }
+
+    async fn fetch(&self, url: &Url) -> Result<Vec<u8>, PasteError> {
+        let key = url.path_segments().and_then(|mut segments| segments.next_back())
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| PasteError::InvalidResponse(format!("{} is not a haste URL", url)))?;
+        // Keys may carry an extension for highlighting, which the raw endpoint doesn't know about
+        let key = key.split('.').next().unwrap_or(key);
+        let raw = self.host.join(&format!("raw/{}", key)).map_err(|e| PasteError::InvalidResponse(e.to_string()))?;
+        let res = super::get_raw(&self.client, raw, url).await?;
+        Ok(res.bytes().await?.to_vec())
+    }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
+    // The document is the whole request body
+    fn fields(&self, _opts: &UploadOptions) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+}
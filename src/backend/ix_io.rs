@@ -0,0 +1,52 @@
+use super::{PasteBackend, UploadOptions, Uploaded};
+use crate::{ExpireSpec, PasteError};
+use reqwest::multipart::Form;
+use reqwest::Url;
+
+const BASE_URL: &str = "http://ix.io";
+
+/// ix.io, which takes the content as the `f:1` form field and answers with its URL.
+pub struct IxIoBackend {
+    client: reqwest::Client,
+}
+
+impl IxIoBackend {
+    pub fn new(client: reqwest::Client) -> Self {
+        IxIoBackend { client }
+    }
+}
+
+#[async_trait::async_trait]
+impl PasteBackend for IxIoBackend {
+    async fn upload(&self, content: &str, opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        let fields = self.fields(opts);
+        tracing::debug!("submitting {} bytes with {:?}", content.len(), fields);
+        let mut form = Form::new().text("f:1", content.to_string());
+        for (name, value) in fields {
+            form = form.text(name, value);
+        }
+
+        let res = self.client.post(BASE_URL)
+            .multipart(form)
+            .send()
+            .await?;
+        tracing::debug!("ix.io returned {}", res.status());
+        let res = super::check_status(res)?;
+        let url = super::url_from_body(&res.text().await?)?;
+        Ok(Uploaded { expires: (opts.expire == ExpireSpec::Once).then_some(ExpireSpec::Once), ..url.into() })
+    }
+
+    async fn fetch(&self, url: &Url) -> Result<Vec<u8>, PasteError> {
+        let res = super::get_raw(&self.client, url.clone(), url).await?;
+        Ok(res.bytes().await?.to_vec())
+    }
+
+    // ix.io can only limit how often a paste is read, so a one-time paste is one read
+    fn fields(&self, opts: &UploadOptions) -> Vec<(&'static str, String)> {
+        if opts.expire == ExpireSpec::Once {
+            return vec![("read:1", "1".to_string())];
+        }
+        tracing::warn!("ix.io has no expire time, only one-time pastes; keeping the paste indefinitely");
+        Vec::new()
+    }
+}
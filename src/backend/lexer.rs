@@ -0,0 +1,56 @@
+// Lexer names as other services spell them. Ours are dpaste's, the software behind
+// paste.mozilla.org; each table lists the lexers a service names differently, with `None` for
+// those it has no equivalent of. Lexers missing from a table are called the same there.
+
+/// Pygments, which dpaste.com and sprunge.us highlight with. Our `_` lexers are dpaste's own.
+pub(crate) const PYGMENTS: &[(&str, Option<&str>)] = &[
+    ("_text", None),
+    ("_code", None),
+    ("_markdown", Some("markdown")),
+    ("_rst", Some("rst")),
+    ("coffee-script", Some("coffeescript")),
+    ("dker", Some("docker")),
+    ("ipythonconsole", None),
+    ("js", Some("javascript")),
+    ("rb", Some("ruby")),
+    ("sol", Some("solidity")),
+];
+
+/// pastebin.com's `api_paste_format` names, from GeSHi. Formats it lacks are uploaded as text.
+pub(crate) const PASTEBIN: &[(&str, Option<&str>)] = &[
+    ("_text", None),
+    ("_code", None),
+    ("_markdown", Some("markdown")),
+    ("_rst", None),
+    ("bat", Some("dos")),
+    ("coffee-script", Some("coffeescript")),
+    ("common-lisp", Some("lisp")),
+    ("console", None),
+    ("dker", None),
+    ("elixir", None),
+    ("handlebars", None),
+    ("html", Some("html5")),
+    ("html+django", Some("django")),
+    ("ipythonconsole", None),
+    ("irc", None),
+    ("js", Some("javascript")),
+    ("jsx", Some("javascript")),
+    ("less", None),
+    ("numpy", Some("python")),
+    ("objective-c", Some("objc")),
+    ("rb", Some("ruby")),
+    ("rst", None),
+    ("sass", None),
+    ("scss", None),
+    ("sol", None),
+    ("tex", Some("latex")),
+    ("xslt", Some("xml")),
+];
+
+/// Looks `canonical`, one of `SUPPORTED_LANG`, up in a table like `PYGMENTS`.
+pub(crate) fn translate(table: &[(&str, Option<&str>)], canonical: &str) -> Option<String> {
+    match table.iter().find(|(ours, _)| *ours == canonical) {
+        Some((_, theirs)) => theirs.map(str::to_string),
+        None => Some(canonical.to_string()),
+    }
+}
@@ -0,0 +1,160 @@
+mod dpaste;
+mod fallback;
+mod gist;
+mod haste;
+mod ix_io;
+mod lexer;
+mod mozilla;
+mod null_pointer;
+mod pastebin_com;
+mod privatebin;
+mod retry;
+mod sprunge;
+mod termbin;
+mod webhook;
+
+pub use dpaste::{DpasteBackend, DPASTE_URL};
+pub use fallback::Fallback;
+pub use gist::GistBackend;
+pub use haste::{HasteBackend, DEFAULT_HASTE_HOST};
+pub use ix_io::IxIoBackend;
+pub use mozilla::{extract_csrf_token, MozillaPaste, BASE_URL};
+pub use null_pointer::NullPointerBackend;
+pub use pastebin_com::{PastebinComBackend, Visibility, PASTEBIN_URL};
+pub use privatebin::PrivateBinBackend;
+pub use retry::Retry;
+pub use sprunge::SprungeBackend;
+pub use termbin::TermbinBackend;
+pub use webhook::{WebhookBackend, DEFAULT_URL_POINTER};
+
+use crate::{ExpireSpec, PasteError};
+use reqwest::Url;
+
+#[derive(Debug, Clone, Default)]
+pub struct UploadOptions {
+    pub expire: ExpireSpec,
+    /// `expire` was asked for, so a service that can't honor it should say so
+    pub explicit_expire: bool,
+    /// Resolved lexer, always one of `SUPPORTED_LANG`
+    pub lang: String,
+    pub title: String,
+    /// Base name of the uploaded file, `None` for stdin
+    pub filename: Option<String>,
+}
+
+/// A paste the service created.
+#[derive(Debug, Clone)]
+pub struct Uploaded {
+    pub url: Url,
+    /// Secret the service handed out for deleting the paste, if it does that
+    pub delete_token: Option<String>,
+    /// Name of the backend that created the paste, when `Fallback` picked one of several
+    pub backend: Option<String>,
+    /// The expire time the service applied, which may be rounded from the one asked for. `None`
+    /// if it keeps pastes for as long as it likes.
+    pub expires: Option<ExpireSpec>,
+}
+
+impl From<Url> for Uploaded {
+    fn from(url: Url) -> Self {
+        Uploaded { url, delete_token: None, backend: None, expires: None }
+    }
+}
+
+// GETs `url`, turning a 404 into `NotFound` for the paste at `paste`
+async fn get_raw(client: &reqwest::Client, url: Url, paste: &Url) -> Result<reqwest::Response, PasteError> {
+    tracing::debug!("fetching {}", url);
+    let res = client.get(url).send().await?;
+    tracing::debug!("{} returned {}", res.url(), res.status());
+    if res.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(PasteError::NotFound(paste.clone()));
+    }
+    check_status(res)
+}
+
+// `error_for_status`, except that a 429 keeps the `Retry-After` the service sent with it
+fn check_status(res: reqwest::Response) -> Result<reqwest::Response, PasteError> {
+    rate_limited(&res)?;
+    Ok(res.error_for_status()?)
+}
+
+// For services whose errors say more in the body than `check_status` would
+fn rate_limited(res: &reqwest::Response) -> Result<(), PasteError> {
+    if res.status() != reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Ok(());
+    }
+    let retry_after = res.headers().get(reqwest::header::RETRY_AFTER).and_then(|value| value.to_str().ok()).and_then(parse_retry_after);
+    Err(PasteError::RateLimited { retry_after })
+}
+
+// `Retry-After` is either a number of seconds or an HTTP date
+fn parse_retry_after(value: &str) -> Option<std::time::Duration> {
+    if let Ok(secs) = value.trim().parse() {
+        return Some(std::time::Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    Some((at.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().unwrap_or_default())
+}
+
+// Services that answer an upload with nothing but the paste's URL
+fn url_from_body(body: &str) -> Result<Url, PasteError> {
+    let url = body.trim();
+    Url::parse(url).map_err(|_| PasteError::InvalidResponse(url.to_string()))
+}
+
+// Checks a self-hosted instance's URL and gives it a trailing slash, so joining appends to its
+// path instead of replacing the last segment
+fn base_url(mut url: Url) -> Result<Url, PasteError> {
+    if !matches!(url.scheme(), "http" | "https") || url.cannot_be_a_base() {
+        return Err(PasteError::InvalidBaseUrl(url));
+    }
+    if !url.path().ends_with('/') {
+        url.set_path(&format!("{}/", url.path()));
+    }
+    Ok(url)
+}
+
+/// A paste service content can be uploaded to.
+#[async_trait::async_trait]
+pub trait PasteBackend: Send + Sync {
+    async fn upload(&self, content: &str, opts: &UploadOptions) -> Result<Uploaded, PasteError>;
+
+    /// Fields besides the content that an upload with `opts` submits, as sent to the service.
+    fn fields(&self, opts: &UploadOptions) -> Vec<(&'static str, String)>;
+
+    /// Uploads raw bytes. Text-only backends reject content that isn't valid UTF-8.
+    async fn upload_bytes(&self, content: &[u8], opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        let content = std::str::from_utf8(content).map_err(|_| PasteError::NotUtf8)?;
+        self.upload(content, opts).await
+    }
+
+    /// Deletes the paste at `url`, with the token from its upload for services that hand one out.
+    async fn delete(&self, _url: &Url, _token: Option<&str>) -> Result<(), PasteError> {
+        Err(PasteError::Unsupported("deleting pastes"))
+    }
+
+    /// Downloads the raw content of the paste at `url`.
+    async fn fetch(&self, url: &Url) -> Result<Vec<u8>, PasteError>;
+
+    /// Uploads `len` bytes from `body` without holding them in memory, for backends that can stream.
+    /// The length is `None` for piped input, which is only known once it has all been sent.
+    async fn upload_stream(&self, _body: reqwest::Body, _len: Option<u64>, _opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        Err(PasteError::Unsupported("streaming uploads"))
+    }
+
+    /// Whether `upload_stream` is implemented.
+    fn supports_streaming(&self) -> bool {
+        false
+    }
+
+    /// Whether `upload_bytes` accepts arbitrary binary content.
+    fn supports_binary(&self) -> bool {
+        false
+    }
+
+    /// This service's name for the lexer `canonical`, one of `SUPPORTED_LANG`. `None` when it has
+    /// no equivalent, or no lexers at all, and the paste is highlighted as plain text if at all.
+    fn translate_lexer(&self, _canonical: &str) -> Option<String> {
+        None
+    }
+}
@@ -0,0 +1,224 @@
+use super::{PasteBackend, UploadOptions, Uploaded};
+use crate::{Expire, ExpireSpec, PasteError, SUPPORTED_EXPIRE};
+use reqwest::{StatusCode, Url};
+use std::path::{Path, PathBuf};
+
+pub const BASE_URL: &str = "https://paste.mozilla.org/";
+
+/// paste.mozilla.org, a dpaste instance behind a Django CSRF-protected form.
+/// Self-hosted instances with the same form flow work too.
+///
+/// The CSRF token and the cookies it belongs to are fetched once and reused for every upload
+/// through the same instance. With a session cache they are kept across runs as well.
+pub struct MozillaPaste {
+    client: reqwest::Client,
+    base_url: Url,
+    session: tokio::sync::Mutex<Option<Session>>,
+    session_cache: Option<PathBuf>,
+}
+
+// What a form submission needs besides the content, as stored in the session cache
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct Session {
+    base_url: String,
+    /// `Cookie` header value for the cookies the form page set
+    cookie: String,
+    token: String,
+}
+
+impl MozillaPaste {
+    pub fn new(client: reqwest::Client) -> Self {
+        MozillaPaste { client, base_url: BASE_URL.parse().unwrap(), session: Default::default(), session_cache: None }
+    }
+
+    pub fn with_base_url(client: reqwest::Client, base_url: Url) -> Result<Self, PasteError> {
+        Ok(MozillaPaste { client, base_url: super::base_url(base_url)?, session: Default::default(), session_cache: None })
+    }
+
+    /// Keeps the CSRF session in `path` between runs, saving a request per run while it's valid.
+    pub fn with_session_cache(mut self, path: PathBuf) -> Self {
+        self.session_cache = Some(path);
+        self
+    }
+
+    /// Default location of the session cache, e.g. ~/.cache/zero-paste/session.json on Linux.
+    pub fn session_cache_path() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("zero-paste").join("session.json"))
+    }
+
+    async fn session(&self) -> Result<Session, PasteError> {
+        // Holding the lock across the fetch makes concurrent uploads wait for a single request
+        let mut session = self.session.lock().await;
+        if let Some(session) = &*session {
+            tracing::trace!("reusing CSRF token");
+            return Ok(session.clone());
+        }
+
+        // A cache written for another instance is no good here
+        if let Some(cached) = self.session_cache.as_deref().and_then(read_session) {
+            if cached.base_url == self.base_url.as_str() {
+                tracing::debug!("using the cached CSRF session");
+                *session = Some(cached.clone());
+                return Ok(cached);
+            }
+        }
+
+        let fresh = self.fetch_session().await?;
+        if let Some(path) = &self.session_cache {
+            write_session(path, &fresh);
+        }
+        *session = Some(fresh.clone());
+        Ok(fresh)
+    }
+
+    /// Fetches a fresh CSRF token from the form page without creating a paste. Neither the
+    /// session cache nor the session of this backend are used or updated.
+    pub async fn fetch_token(&self) -> Result<String, PasteError> {
+        Ok(self.fetch_session().await?.token)
+    }
+
+    async fn fetch_session(&self) -> Result<Session, PasteError> {
+        tracing::debug!("fetching CSRF token from {}", self.base_url);
+        let res = self.client.get(self.base_url.as_str())
+            .send()
+            .await?;
+        tracing::debug!("CSRF page returned {}", res.status());
+
+        let cookie = res.cookies().map(|cookie| format!("{}={}", cookie.name(), cookie.value())).collect::<Vec<_>>().join("; ");
+        let html = res.text().await?;
+        Ok(Session { base_url: self.base_url.to_string(), cookie, token: extract_csrf_token(&html)? })
+    }
+
+    // Drops a session the server turned down, unless another upload already replaced it
+    async fn forget_session(&self, stale: &Session) {
+        let mut session = self.session.lock().await;
+        if session.as_ref().is_some_and(|session| session.token == stale.token) {
+            *session = None;
+            if let Some(path) = &self.session_cache {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+
+    async fn submit(&self, session: &Session, content: &str, opts: &UploadOptions) -> Result<reqwest::Response, PasteError> {
+        let base_url = self.base_url.as_str();
+        let mut form: std::collections::HashMap<_, _> = self.fields(opts).into_iter().collect();
+        tracing::debug!("submitting {} bytes with {:?}", content.len(), form);
+        form.insert("csrfmiddlewaretoken", session.token.clone());
+        form.insert("content", content.to_string());
+
+        let mut req = self.client.post(base_url)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("Referer", base_url)
+            .header("Origin", base_url);
+        // The client's cookie store doesn't know the cookies of a cached session
+        if !session.cookie.is_empty() {
+            req = req.header("Cookie", &session.cookie);
+        }
+        Ok(req.form(&form).send().await?)
+    }
+}
+
+fn read_session(path: &Path) -> Option<Session> {
+    let content = std::fs::read(path).ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+// The cache only saves a request, so failing to write it isn't worth bothering anyone about
+fn write_session(path: &Path, session: &Session) {
+    let result = path.parent().map_or(Ok(()), std::fs::create_dir_all).and_then(|()| {
+        std::fs::write(path, serde_json::to_vec(session).map_err(std::io::Error::other)?)
+    });
+    if let Err(e) = result {
+        tracing::debug!("could not cache the CSRF session in {}: {}", path.display(), e);
+    }
+}
+
+#[async_trait::async_trait]
+impl PasteBackend for MozillaPaste {
+    async fn upload(&self, content: &str, opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        let session = self.session().await?;
+        let mut res = self.submit(&session, content, opts).await?;
+        // Django answers an expired or unknown CSRF token with 403, whether it came from the cache
+        // or went stale while a large file was read
+        if res.status() == StatusCode::FORBIDDEN {
+            tracing::debug!("the CSRF token was rejected, fetching a new one");
+            self.forget_session(&session).await;
+            let session = self.session().await?;
+            res = self.submit(&session, content, opts).await?;
+        }
+        super::rate_limited(&res)?;
+        let status = res.status();
+        let url = res.url().clone();
+        tracing::debug!("paste form returned {} at {}", status, url);
+
+        // A failed submission re-renders the form instead of redirecting to the new paste
+        if !(status.is_success() || status.is_redirection()) || url == self.base_url {
+            let html = res.text().await.unwrap_or_default();
+            let reason = extract_form_errors(&html).unwrap_or_else(|| "no paste was created".to_string());
+            return Err(PasteError::UploadRejected { status, reason });
+        }
+
+        Ok(Uploaded { expires: Some(Expire::nearest(opts.expire).into()), ..url.into() })
+    }
+
+    // dpaste serves the plain content under `<paste>/raw`
+    async fn fetch(&self, url: &Url) -> Result<Vec<u8>, PasteError> {
+        let mut raw = url.clone();
+        let path = url.path().trim_end_matches('/');
+        if !path.ends_with("/raw") {
+            raw.set_path(&format!("{}/raw", path));
+        }
+        let res = super::get_raw(&self.client, raw, url).await?;
+        Ok(res.bytes().await?.to_vec())
+    }
+
+    fn fields(&self, opts: &UploadOptions) -> Vec<(&'static str, String)> {
+        vec![
+            ("expires", expires_field(opts.expire).to_string()),
+            ("lexer", opts.lang.clone()),
+            ("title", opts.title.clone()),
+        ]
+    }
+
+    // Our lexers are the ones dpaste offers
+    fn translate_lexer(&self, canonical: &str) -> Option<String> {
+        Some(canonical.to_string())
+    }
+}
+
+/// Extracts the `csrfmiddlewaretoken` the paste form has to be submitted with.
+pub fn extract_csrf_token(html: &str) -> Result<String, PasteError> {
+    let document = dom_query::Document::from(html);
+    document.select("input[name=csrfmiddlewaretoken]")
+        .attr("value")
+        .map(|token| token.to_string())
+        .ok_or(PasteError::CsrfTokenMissing)
+}
+
+// Validation messages Django renders next to the fields of a rejected form
+fn extract_form_errors(html: &str) -> Option<String> {
+    let document = dom_query::Document::from(html);
+    let errors: Vec<_> = document.select(".errorlist li")
+        .iter()
+        .map(|error| error.text().trim().to_string())
+        .filter(|error| !error.is_empty())
+        .collect();
+    (!errors.is_empty()).then(|| errors.join("; "))
+}
+
+// Value expected by the `expires` field of the paste form, which only offers a few presets
+fn expires_field(spec: ExpireSpec) -> &'static str {
+    let expire = Expire::nearest(spec);
+    if ExpireSpec::from(expire) != spec {
+        tracing::warn!("the paste service only supports {:?}, rounding {} to {}", SUPPORTED_EXPIRE, spec, expire);
+    }
+
+    match expire {
+        Expire::Once => "onetime",
+        Expire::Hour => "3600",
+        Expire::Day => "86400",
+        Expire::Week => "604800",
+        Expire::ThreeWeeks => "2073600",
+    }
+}
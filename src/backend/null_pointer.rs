@@ -0,0 +1,114 @@
+use super::{PasteBackend, UploadOptions, Uploaded};
+use crate::{ExpireSpec, PasteError};
+use reqwest::multipart::{Form, Part};
+use reqwest::Url;
+
+const BASE_URL: &str = "https://0x0.st";
+
+/// The Null Pointer (0x0.st), a file host that takes arbitrary binary content.
+pub struct NullPointerBackend {
+    client: reqwest::Client,
+}
+
+impl NullPointerBackend {
+    pub fn new(client: reqwest::Client) -> Self {
+        NullPointerBackend { client }
+    }
+
+    async fn submit(&self, mut file: Part, opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        let (filename, fields): (Vec<_>, Vec<_>) = self.fields(opts).into_iter().partition(|(name, _)| *name == "filename");
+        tracing::debug!("form fields {:?}", fields);
+        if let Some((_, name)) = filename.into_iter().next() {
+            file = file.file_name(name);
+        }
+        let mut form = Form::new().part("file", file);
+        for (name, value) in fields {
+            form = form.text(name, value);
+        }
+
+        let res = self.client.post(BASE_URL)
+            .multipart(form)
+            .send()
+            .await?;
+        tracing::debug!("0x0.st returned {}", res.status());
+        let res = super::check_status(res)?;
+
+        // The management token only comes as a header, and only for new files
+        let delete_token = res.headers().get("X-Token").and_then(|token| token.to_str().ok()).map(str::to_string);
+        let body = res.text().await?;
+        let url = body.trim();
+        let url = Url::parse(url).map_err(|_| PasteError::InvalidResponse(url.to_string()))?;
+        let expires = expires_hours(opts.expire).map(|hours| ExpireSpec::Seconds(hours * 3600));
+        Ok(Uploaded { url, delete_token, backend: None, expires })
+    }
+}
+
+#[async_trait::async_trait]
+impl PasteBackend for NullPointerBackend {
+    async fn upload(&self, content: &str, opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        self.upload_bytes(content.as_bytes(), opts).await
+    }
+
+    async fn upload_bytes(&self, content: &[u8], opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        tracing::debug!("submitting {} bytes", content.len());
+        self.submit(Part::bytes(content.to_vec()), opts).await
+    }
+
+    async fn upload_stream(&self, body: reqwest::Body, len: Option<u64>, opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        match len {
+            Some(len) => {
+                tracing::debug!("streaming {} bytes", len);
+                self.submit(Part::stream_with_length(body, len), opts).await
+            }
+            None => {
+                tracing::debug!("streaming piped input");
+                self.submit(Part::stream(body), opts).await
+            }
+        }
+    }
+
+    async fn delete(&self, url: &Url, token: Option<&str>) -> Result<(), PasteError> {
+        let token = token.ok_or_else(|| PasteError::DeleteTokenMissing(url.clone()))?;
+        let form = Form::new().text("token", token.to_string()).text("delete", "");
+
+        let res = self.client.post(url.clone())
+            .multipart(form)
+            .send()
+            .await?;
+        tracing::debug!("0x0.st returned {}", res.status());
+        super::check_status(res)?;
+        Ok(())
+    }
+
+    // Files are served as they were uploaded
+    async fn fetch(&self, url: &Url) -> Result<Vec<u8>, PasteError> {
+        let res = super::get_raw(&self.client, url.clone(), url).await?;
+        Ok(res.bytes().await?.to_vec())
+    }
+
+    // The file name goes on the `file` part rather than into a field of its own
+    fn fields(&self, opts: &UploadOptions) -> Vec<(&'static str, String)> {
+        let filename = opts.filename.clone().unwrap_or_else(|| "paste.txt".to_string());
+        let mut fields = vec![("filename", filename)];
+
+        // 0x0.st takes the retention in hours and has no notion of burn-after-reading
+        match expires_hours(opts.expire) {
+            Some(hours) => fields.push(("expires", hours.to_string())),
+            None if opts.explicit_expire => tracing::warn!("0x0.st has no one-time pastes, using its default retention"),
+            None => {}
+        }
+        fields
+    }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
+    fn supports_binary(&self) -> bool {
+        true
+    }
+}
+
+fn expires_hours(expire: ExpireSpec) -> Option<u64> {
+    expire.seconds().map(|secs| secs.div_ceil(3600))
+}
@@ -0,0 +1,173 @@
+use super::{PasteBackend, UploadOptions, Uploaded};
+use crate::{ExpireSpec, PasteError};
+use reqwest::Url;
+
+pub const PASTEBIN_URL: &str = "https://pastebin.com/";
+
+// Expire times the API offers besides `N` for never, with their lifetimes
+const EXPIRE_PRESETS: [(&str, u64); 8] = [
+    ("10M", 600),
+    ("1H", 3600),
+    ("1D", 86400),
+    ("1W", 604800),
+    ("2W", 1209600),
+    ("1M", 2592000),
+    ("6M", 15552000),
+    ("1Y", 31536000),
+];
+
+// Lifetime of one-time pastes, which the API has no option for
+const ONE_TIME_FALLBACK: u64 = 600;
+
+// Every failure is answered with this before the reason, often with a 200
+const API_ERROR: &str = "Bad API request, ";
+
+/// Who can find a paste on pastebin.com.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Visibility {
+    /// Listed with the recent pastes
+    Public,
+    /// Only reachable through its URL
+    #[default]
+    Unlisted,
+    /// Only visible to the account it was made with, which needs a user key
+    Private,
+}
+
+impl std::str::FromStr for Visibility {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "public" => Ok(Visibility::Public),
+            "unlisted" => Ok(Visibility::Unlisted),
+            "private" => Ok(Visibility::Private),
+            _ => Err(format!("unknown visibility `{}`, expected public, unlisted or private", s)),
+        }
+    }
+}
+
+/// pastebin.com, through its developer API with the key of an account.
+pub struct PastebinComBackend {
+    client: reqwest::Client,
+    base_url: Url,
+    dev_key: String,
+    /// Logs the paste in to the account, which private pastes need
+    user_key: Option<String>,
+    visibility: Visibility,
+}
+
+impl PastebinComBackend {
+    pub fn new(client: reqwest::Client, dev_key: String, visibility: Visibility) -> Self {
+        PastebinComBackend { client, base_url: PASTEBIN_URL.parse().unwrap(), dev_key, user_key: None, visibility }
+    }
+
+    pub fn with_base_url(mut self, base_url: Url) -> Result<Self, PasteError> {
+        self.base_url = super::base_url(base_url)?;
+        Ok(self)
+    }
+
+    /// Creates the pastes in the account `user_key` was generated for.
+    pub fn with_user_key(mut self, user_key: String) -> Self {
+        self.user_key = Some(user_key);
+        self
+    }
+
+    async fn submit(&self, content: &str, fields: &[(&'static str, String)]) -> Result<Url, PasteError> {
+        let api = self.base_url.join("api/api_post.php").map_err(|e| PasteError::InvalidResponse(e.to_string()))?;
+        let mut form = vec![("api_dev_key", self.dev_key.as_str()), ("api_option", "paste"), ("api_paste_code", content)];
+        form.extend(self.user_key.as_deref().map(|key| ("api_user_key", key)));
+        form.extend(fields.iter().map(|(name, value)| (*name, value.as_str())));
+        let res = self.client.post(api).form(&form).send().await?;
+        tracing::debug!("pastebin.com returned {}", res.status());
+        super::rate_limited(&res)?;
+
+        let status = res.status();
+        let body = res.text().await?;
+        let body = body.trim();
+        if let Some(reason) = body.strip_prefix(API_ERROR) {
+            let reason = match reason {
+                "invalid api_dev_key" => format!("{}; check $PASTEBIN_API_KEY or pastebin.api_key in the config file", reason),
+                _ => reason.to_string(),
+            };
+            return Err(PasteError::UploadRejected { status, reason });
+        }
+        if !status.is_success() {
+            return Err(PasteError::UploadRejected { status, reason: body.to_string() });
+        }
+        super::url_from_body(body)
+    }
+}
+
+#[async_trait::async_trait]
+impl PasteBackend for PastebinComBackend {
+    async fn upload(&self, content: &str, opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        if self.visibility == Visibility::Private && self.user_key.is_none() {
+            return Err(PasteError::MissingCredential { what: "a pastebin.com user key for private pastes", env: "PASTEBIN_USER_KEY", config: "pastebin.user_key" });
+        }
+
+        let mut fields = self.fields(opts);
+        tracing::debug!("form fields {:?}", fields);
+        let url = match self.submit(content, &fields).await {
+            // The list of formats changes now and then, and plain text beats no paste at all
+            Err(PasteError::UploadRejected { reason, .. }) if reason == "invalid api_paste_format" => {
+                tracing::warn!("pastebin.com doesn't know the format for {}, uploading it as plain text", opts.lang);
+                fields.retain(|(name, _)| *name != "api_paste_format");
+                self.submit(content, &fields).await
+            }
+            result => result,
+        }?;
+        let expires = ExpireSpec::Seconds(nearest_preset(opts.expire.seconds().unwrap_or(ONE_TIME_FALLBACK)).1);
+        Ok(Uploaded { expires: Some(expires), ..url.into() })
+    }
+
+    // The plain content is served under `raw/<key>`
+    async fn fetch(&self, url: &Url) -> Result<Vec<u8>, PasteError> {
+        let key = url.path_segments().and_then(|mut segments| segments.next_back()).unwrap_or_default();
+        let raw = url.join(&format!("/raw/{}", key)).map_err(|_| PasteError::InvalidResponse(url.to_string()))?;
+        let res = super::get_raw(&self.client, raw, url).await?;
+        Ok(res.bytes().await?.to_vec())
+    }
+
+    fn fields(&self, opts: &UploadOptions) -> Vec<(&'static str, String)> {
+        let private = match self.visibility {
+            Visibility::Public => "0",
+            Visibility::Unlisted => "1",
+            Visibility::Private => "2",
+        };
+        let mut fields = vec![
+            ("api_paste_expire_date", expire_date(opts.expire).to_string()),
+            ("api_paste_private", private.to_string()),
+        ];
+        if let Some(format) = self.translate_lexer(&opts.lang) {
+            fields.push(("api_paste_format", format));
+        }
+        if !opts.title.is_empty() {
+            fields.push(("api_paste_name", opts.title.clone()));
+        }
+        fields
+    }
+
+    fn translate_lexer(&self, canonical: &str) -> Option<String> {
+        super::lexer::translate(super::lexer::PASTEBIN, canonical)
+    }
+}
+
+// There's no one-time option in the API, so those get the shortest lifetime instead
+fn expire_date(spec: ExpireSpec) -> &'static str {
+    let Some(secs) = spec.seconds() else {
+        tracing::warn!("pastebin.com has no one-time pastes in its API, keeping it for 10 minutes instead");
+        return nearest_preset(ONE_TIME_FALLBACK).0;
+    };
+    let (name, preset) = nearest_preset(secs);
+    if preset != secs {
+        tracing::warn!("pastebin.com only offers {:?}, rounding {} to {}", EXPIRE_PRESETS.map(|(name, _)| name), spec, name);
+    }
+    name
+}
+
+fn nearest_preset(secs: u64) -> (&'static str, u64) {
+    EXPIRE_PRESETS.into_iter()
+        .min_by_key(|(_, preset)| preset.abs_diff(secs))
+        .unwrap()
+}
@@ -0,0 +1,245 @@
+use super::{PasteBackend, UploadOptions, Uploaded};
+use crate::{ExpireSpec, PasteError};
+use reqwest::Url;
+
+// Parameters of PrivateBin's v2 format: PBKDF2-SHA256 rounds, key and tag sizes in bits, and the
+// random IV and salt that go with each paste
+const ITERATIONS: u32 = 100_000;
+const KEY_BITS: u32 = 256;
+const TAG_BITS: u32 = 128;
+const IV_LEN: usize = 16;
+const SALT_LEN: usize = 8;
+
+// Expire times an instance offers out of the box, with their lifetimes
+const EXPIRE_PRESETS: [(&str, u64); 7] = [
+    ("5min", 300),
+    ("10min", 600),
+    ("1hour", 3600),
+    ("1day", 86400),
+    ("1week", 604800),
+    ("1month", 2592000),
+    ("1year", 31536000),
+];
+
+// The 16-byte IV PrivateBin uses rather than the usual 12
+type Cipher = aes_gcm::AesGcm<aes_gcm::aes::Aes256, aes_gcm::aead::consts::U16>;
+
+/// A PrivateBin instance. Content is compressed and encrypted before it leaves the machine, and
+/// the key only travels in the URL fragment, which browsers never send to the server.
+pub struct PrivateBinBackend {
+    client: reqwest::Client,
+    host: Url,
+}
+
+#[derive(serde::Deserialize)]
+struct PrivateBinResponse {
+    status: u8,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    deletetoken: Option<String>,
+    #[serde(default)]
+    adata: Option<serde_json::Value>,
+    #[serde(default)]
+    ct: Option<String>,
+}
+
+impl PrivateBinBackend {
+    pub fn new(client: reqwest::Client, host: Url) -> Result<Self, PasteError> {
+        Ok(PrivateBinBackend { client, host: super::base_url(host)? })
+    }
+
+    // The JSON API shares its URL with the web page and is told apart by this header
+    async fn request(&self, request: reqwest::RequestBuilder) -> Result<(reqwest::StatusCode, PrivateBinResponse), PasteError> {
+        let res = request.header("X-Requested-With", "JSONHttpRequest").send().await?;
+        tracing::debug!("privatebin returned {}", res.status());
+        let res = super::check_status(res)?;
+        Ok((res.status(), res.json().await?))
+    }
+
+    // Pastes are addressed by their ID as the whole query string, as in `https://host/?abcd#key`
+    fn paste_id(url: &Url) -> Result<&str, PasteError> {
+        url.query()
+            .map(|query| query.strip_prefix("pasteid=").unwrap_or(query))
+            .filter(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| PasteError::InvalidResponse(format!("{} is not a PrivateBin URL", url)))
+    }
+}
+
+#[async_trait::async_trait]
+impl PasteBackend for PrivateBinBackend {
+    async fn upload(&self, content: &str, opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        let (body, key) = encrypt(content, opts)?;
+        tracing::debug!("posting {} bytes of ciphertext to {}", body["ct"].as_str().unwrap_or_default().len(), self.host);
+        let (status, res) = self.request(self.client.post(self.host.clone()).json(&body)).await?;
+        if res.status != 0 {
+            return Err(PasteError::UploadRejected { status, reason: res.message.unwrap_or_else(|| "no reason given".to_string()) });
+        }
+
+        let id = res.id.ok_or_else(|| PasteError::InvalidResponse("no paste ID in the response".to_string()))?;
+        let mut url = self.host.clone();
+        url.set_query(Some(&id));
+        url.set_fragment(Some(&bs58::encode(key).into_string()));
+        let expires = match opts.expire.seconds() {
+            Some(secs) => ExpireSpec::Seconds(nearest_preset(secs).1),
+            None => ExpireSpec::Once,
+        };
+        Ok(Uploaded { url, delete_token: res.deletetoken, backend: None, expires: Some(expires) })
+    }
+
+    async fn fetch(&self, url: &Url) -> Result<Vec<u8>, PasteError> {
+        let id = Self::paste_id(url)?;
+        let key = url.fragment()
+            .and_then(|key| bs58::decode(key).into_vec().ok())
+            .ok_or_else(|| PasteError::Decryption(format!("{} has no key in its fragment", url)))?;
+
+        let mut api = self.host.clone();
+        api.set_query(Some(&format!("pasteid={}", id)));
+        tracing::debug!("fetching {}", api);
+        let (_, res) = self.request(self.client.get(api)).await?;
+        if res.status != 0 {
+            return Err(PasteError::NotFound(url.clone()));
+        }
+        let (Some(adata), Some(ct)) = (res.adata, res.ct) else {
+            return Err(PasteError::InvalidResponse("no ciphertext in the response".to_string()));
+        };
+        Ok(decrypt(&ct, &adata, &key)?.into_bytes())
+    }
+
+    async fn delete(&self, url: &Url, token: Option<&str>) -> Result<(), PasteError> {
+        let token = token.ok_or_else(|| PasteError::DeleteTokenMissing(url.clone()))?;
+        let body = serde_json::json!({ "pasteid": Self::paste_id(url)?, "deletetoken": token });
+        let (status, res) = self.request(self.client.post(self.host.clone()).json(&body)).await?;
+        if res.status != 0 {
+            return Err(PasteError::UploadRejected { status, reason: res.message.unwrap_or_else(|| "no reason given".to_string()) });
+        }
+        Ok(())
+    }
+
+    // Everything else is inside the ciphertext
+    fn fields(&self, opts: &UploadOptions) -> Vec<(&'static str, String)> {
+        vec![
+            ("formatter", formatter(&opts.lang).to_string()),
+            ("expire", expire_field(opts.expire).to_string()),
+            ("burnafterreading", (opts.expire == ExpireSpec::Once).to_string()),
+        ]
+    }
+}
+
+// PrivateBin only tells plain text, markdown and code apart, and guesses the language of code
+fn formatter(lang: &str) -> &'static str {
+    match lang {
+        "_text" => "plaintext",
+        "_markdown" => "markdown",
+        _ => "syntaxhighlighting",
+    }
+}
+
+// One-time pastes still need an expire time, for when nobody ever opens them
+fn expire_field(spec: ExpireSpec) -> &'static str {
+    let Some(secs) = spec.seconds() else {
+        return "1week";
+    };
+    let (name, preset) = nearest_preset(secs);
+    if preset != secs {
+        tracing::warn!("PrivateBin only offers {:?}, rounding {} to {}", EXPIRE_PRESETS.map(|(name, _)| name), spec, name);
+    }
+    name
+}
+
+fn nearest_preset(secs: u64) -> (&'static str, u64) {
+    EXPIRE_PRESETS.into_iter()
+        .min_by_key(|(_, preset)| preset.abs_diff(secs))
+        .unwrap()
+}
+
+// Builds the JSON body of a new paste, returning it with the key that decrypts it
+fn encrypt(text: &str, opts: &UploadOptions) -> Result<(serde_json::Value, [u8; 32]), PasteError> {
+    use aes_gcm::aead::{Aead, KeyInit, Payload};
+    use base64::Engine;
+    use std::io::Write;
+
+    let failed = |e: &dyn std::fmt::Display| PasteError::Encryption(e.to_string());
+    let (mut key, mut iv, mut salt) = ([0; 32], [0; IV_LEN], [0; SALT_LEN]);
+    getrandom::fill(&mut key)
+        .and_then(|_| getrandom::fill(&mut iv))
+        .and_then(|_| getrandom::fill(&mut salt))
+        .map_err(|e| failed(&e))?;
+
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let burn = u8::from(opts.expire == ExpireSpec::Once);
+    let spec = serde_json::json!([b64.encode(iv), b64.encode(salt), ITERATIONS, KEY_BITS, TAG_BITS, "aes", "gcm", "zlib"]);
+    // The discussion flag is left off, as PrivateBin requires it to be for one-time pastes
+    let adata = serde_json::json!([spec, formatter(&opts.lang), 0, burn]);
+
+    // "zlib" in PrivateBin's terms is a raw deflate stream, without the zlib header
+    let mut deflate = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+    deflate.write_all(serde_json::json!({ "paste": text }).to_string().as_bytes())?;
+    let compressed = deflate.finish()?;
+
+    let cipher = Cipher::new(&derive_key(&key, &salt, ITERATIONS).into());
+    // The authenticated data is adata exactly as serialized in the request
+    let aad = adata.to_string();
+    let ct = cipher.encrypt(&iv.into(), Payload { msg: &compressed, aad: aad.as_bytes() }).map_err(|e| failed(&e))?;
+
+    let body = serde_json::json!({
+        "v": 2,
+        "adata": adata,
+        "ct": b64.encode(ct),
+        "meta": { "expire": expire_field(opts.expire) },
+    });
+    Ok((body, key))
+}
+
+// Reverses `encrypt` for a paste made by any PrivateBin client, which may have picked other
+// parameters or left the content uncompressed
+fn decrypt(ct: &str, adata: &serde_json::Value, key: &[u8]) -> Result<String, PasteError> {
+    use aes_gcm::aead::{Aead, KeyInit, Payload};
+    use base64::Engine;
+    use std::io::Read;
+
+    let failed = |reason: &str| PasteError::Decryption(reason.to_string());
+    let spec = adata.get(0).and_then(|spec| spec.as_array()).ok_or_else(|| failed("the paste has no encryption parameters"))?;
+    let field = |i: usize| spec.get(i).and_then(|value| value.as_str());
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let iv = field(0).and_then(|iv| b64.decode(iv).ok()).filter(|iv| iv.len() == IV_LEN);
+    let salt = field(1).and_then(|salt| b64.decode(salt).ok());
+    let iterations = spec.get(2).and_then(|n| n.as_u64()).and_then(|n| u32::try_from(n).ok());
+    let (Some(iv), Some(salt), Some(iterations)) = (iv, salt, iterations) else {
+        return Err(failed("the paste's encryption parameters are malformed"));
+    };
+    if (field(5), field(6)) != (Some("aes"), Some("gcm")) || spec.get(3) != Some(&KEY_BITS.into()) || spec.get(4) != Some(&TAG_BITS.into()) {
+        return Err(failed("the paste uses a cipher other than AES-256-GCM"));
+    }
+
+    let ct = b64.decode(ct).map_err(|e| PasteError::Decryption(e.to_string()))?;
+    let cipher = Cipher::new(&derive_key(key, &salt, iterations).into());
+    let iv: [u8; IV_LEN] = iv.try_into().unwrap();
+    let aad = adata.to_string();
+    let plaintext = cipher.decrypt(&iv.into(), Payload { msg: &ct, aad: aad.as_bytes() })
+        .map_err(|_| failed("wrong key or corrupted paste"))?;
+
+    let plaintext = match field(7) {
+        Some("zlib") => {
+            let mut inflated = Vec::new();
+            flate2::read::DeflateDecoder::new(&plaintext[..]).read_to_end(&mut inflated)?;
+            inflated
+        }
+        Some("none") => plaintext,
+        _ => return Err(failed("the paste uses an unknown compression")),
+    };
+
+    #[derive(serde::Deserialize)]
+    struct Message {
+        paste: String,
+    }
+    let message: Message = serde_json::from_slice(&plaintext).map_err(|e| PasteError::Decryption(e.to_string()))?;
+    Ok(message.paste)
+}
+
+// An AES-256 key, stretched with PBKDF2-HMAC-SHA256 like PrivateBin's own client does
+fn derive_key(secret: &[u8], salt: &[u8], iterations: u32) -> [u8; 32] {
+    pbkdf2::pbkdf2_hmac_array::<sha2::Sha256, 32>(secret, salt, iterations)
+}
@@ -0,0 +1,85 @@
+use super::{PasteBackend, UploadOptions, Uploaded};
+use crate::PasteError;
+use reqwest::Url;
+use std::time::Duration;
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+// A service that wants to be left alone for longer than this gets an error instead of a hung upload
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Retries uploads of the wrapped backend that failed on connection errors or timeouts, or were
+/// turned away with 429 Too Many Requests; those wait as long as the `Retry-After` header says.
+/// Other responses with an error status are never retried since they won't go away on their own.
+pub struct Retry {
+    inner: Box<dyn PasteBackend>,
+    retries: u32,
+}
+
+impl Retry {
+    pub fn new(inner: Box<dyn PasteBackend>, retries: u32) -> Self {
+        Retry { inner, retries }
+    }
+}
+
+// How long to wait before retrying after `e`, or `None` if retrying won't help
+fn retry_delay(e: &PasteError, backoff: Duration) -> Option<Duration> {
+    match e {
+        PasteError::Http(e) if e.is_connect() || e.is_timeout() => Some(backoff),
+        PasteError::RateLimited { retry_after: None } => Some(backoff),
+        PasteError::RateLimited { retry_after: Some(after) } => (*after <= MAX_RETRY_AFTER).then_some(*after),
+        _ => None,
+    }
+}
+
+#[async_trait::async_trait]
+impl PasteBackend for Retry {
+    async fn upload(&self, content: &str, opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        self.upload_bytes(content.as_bytes(), opts).await
+    }
+
+    async fn upload_bytes(&self, content: &[u8], opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        let mut backoff = INITIAL_BACKOFF;
+        for _ in 0..self.retries {
+            let e = match self.inner.upload_bytes(content, opts).await {
+                Err(e) => e,
+                uploaded => return uploaded,
+            };
+            let Some(delay) = retry_delay(&e, backoff) else {
+                return Err(e);
+            };
+            tracing::warn!("{}, retrying in {:?}", e, delay);
+            tokio::time::sleep(delay).await;
+            backoff *= 2;
+        }
+        self.inner.upload_bytes(content, opts).await
+    }
+
+    // A stream can only be sent once, so there's nothing to retry with
+    async fn upload_stream(&self, body: reqwest::Body, len: Option<u64>, opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        self.inner.upload_stream(body, len, opts).await
+    }
+
+    async fn delete(&self, url: &Url, token: Option<&str>) -> Result<(), PasteError> {
+        self.inner.delete(url, token).await
+    }
+
+    async fn fetch(&self, url: &Url) -> Result<Vec<u8>, PasteError> {
+        self.inner.fetch(url).await
+    }
+
+    fn fields(&self, opts: &UploadOptions) -> Vec<(&'static str, String)> {
+        self.inner.fields(opts)
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn supports_binary(&self) -> bool {
+        self.inner.supports_binary()
+    }
+
+    fn translate_lexer(&self, canonical: &str) -> Option<String> {
+        self.inner.translate_lexer(canonical)
+    }
+}
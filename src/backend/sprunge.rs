@@ -0,0 +1,60 @@
+use super::{PasteBackend, UploadOptions, Uploaded};
+use crate::{ExpireSpec, PasteError};
+use reqwest::multipart::Form;
+use reqwest::Url;
+
+const BASE_URL: &str = "http://sprunge.us";
+
+/// sprunge.us, which takes the content as a single form field and answers with its URL.
+///
+/// It highlights with Pygments when the lexer is added to the URL as `?lexer`, so that's where
+/// the lexer goes rather than into the upload.
+pub struct SprungeBackend {
+    client: reqwest::Client,
+}
+
+impl SprungeBackend {
+    pub fn new(client: reqwest::Client) -> Self {
+        SprungeBackend { client }
+    }
+}
+
+#[async_trait::async_trait]
+impl PasteBackend for SprungeBackend {
+    async fn upload(&self, content: &str, opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        if opts.expire != ExpireSpec::Once {
+            tracing::info!("sprunge.us has no expire time, ignoring it");
+        }
+
+        tracing::debug!("submitting {} bytes", content.len());
+        let form = Form::new().text("sprunge", content.to_string());
+        let res = self.client.post(BASE_URL)
+            .multipart(form)
+            .send()
+            .await?;
+        tracing::debug!("sprunge.us returned {}", res.status());
+        let res = super::check_status(res)?;
+
+        let mut url = super::url_from_body(&res.text().await?)?;
+        if let Some(lexer) = self.translate_lexer(&opts.lang) {
+            url.set_query(Some(&lexer));
+        }
+        Ok(url.into())
+    }
+
+    // Without the lexer query the content is served as plain text
+    async fn fetch(&self, url: &Url) -> Result<Vec<u8>, PasteError> {
+        let mut raw = url.clone();
+        raw.set_query(None);
+        let res = super::get_raw(&self.client, raw, url).await?;
+        Ok(res.bytes().await?.to_vec())
+    }
+
+    fn fields(&self, _opts: &UploadOptions) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+
+    fn translate_lexer(&self, canonical: &str) -> Option<String> {
+        super::lexer::translate(super::lexer::PYGMENTS, canonical)
+    }
+}
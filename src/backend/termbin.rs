@@ -0,0 +1,59 @@
+use super::{PasteBackend, UploadOptions, Uploaded};
+use crate::{ExpireSpec, PasteError};
+use reqwest::Url;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+const ADDRESS: &str = "termbin.com:9999";
+
+// termbin has no HTTP in front, so the client's timeout doesn't apply
+const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// termbin.com, a netcat pastebin: the content is written to a plain TCP socket, and once that is
+/// shut down for writing the service answers with the paste's URL.
+pub struct TermbinBackend {
+    client: reqwest::Client,
+}
+
+impl TermbinBackend {
+    /// `client` is only used to fetch pastes, which termbin serves over HTTP.
+    pub fn new(client: reqwest::Client) -> Self {
+        TermbinBackend { client }
+    }
+
+    async fn submit(&self, content: &[u8]) -> Result<Url, PasteError> {
+        tracing::debug!("connecting to {}", ADDRESS);
+        let mut socket = tokio::net::TcpStream::connect(ADDRESS).await?;
+        tracing::debug!("sending {} bytes", content.len());
+        socket.write_all(content).await?;
+        socket.shutdown().await?;
+
+        let mut response = Vec::new();
+        socket.read_to_end(&mut response).await?;
+        // The URL comes with a newline and a NUL byte after it
+        let response = String::from_utf8_lossy(&response);
+        super::url_from_body(response.trim_end_matches(['\0', '\n', '\r']))
+    }
+}
+
+#[async_trait::async_trait]
+impl PasteBackend for TermbinBackend {
+    async fn upload(&self, content: &str, opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        if opts.expire != ExpireSpec::Once || opts.lang != "_code" {
+            tracing::info!("termbin has no expire time or lexer, ignoring them");
+        }
+
+        let url = tokio::time::timeout(TIMEOUT, self.submit(content.as_bytes()))
+            .await
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, format!("{} did not answer in time", ADDRESS)))??;
+        Ok(url.into())
+    }
+
+    async fn fetch(&self, url: &Url) -> Result<Vec<u8>, PasteError> {
+        let res = super::get_raw(&self.client, url.clone(), url).await?;
+        Ok(res.bytes().await?.to_vec())
+    }
+
+    fn fields(&self, _opts: &UploadOptions) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+}
@@ -0,0 +1,61 @@
+use super::{PasteBackend, UploadOptions, Uploaded};
+use crate::PasteError;
+use reqwest::Url;
+
+/// Where `WebhookBackend` looks for the paste URL in a response, unless told otherwise.
+pub const DEFAULT_URL_POINTER: &str = "/url";
+
+/// Any endpoint that takes the paste as a JSON POST of `content`, `lang` and `filename`, and
+/// answers with JSON that has the paste URL at a JSON pointer, `/url` by default.
+pub struct WebhookBackend {
+    client: reqwest::Client,
+    url: Url,
+    url_pointer: String,
+}
+
+impl WebhookBackend {
+    pub fn new(client: reqwest::Client, url: Url, url_pointer: Option<String>) -> Result<Self, PasteError> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(PasteError::InvalidBaseUrl(url));
+        }
+        Ok(WebhookBackend { client, url, url_pointer: url_pointer.unwrap_or_else(|| DEFAULT_URL_POINTER.to_string()) })
+    }
+}
+
+#[async_trait::async_trait]
+impl PasteBackend for WebhookBackend {
+    async fn upload(&self, content: &str, opts: &UploadOptions) -> Result<Uploaded, PasteError> {
+        let payload = serde_json::json!({ "content": content, "lang": opts.lang, "filename": opts.filename });
+        tracing::debug!("posting {} bytes to {}", content.len(), self.url);
+        let res = self.client.post(self.url.clone())
+            .json(&payload)
+            .send()
+            .await?;
+        tracing::debug!("webhook returned {}", res.status());
+        let res = super::check_status(res)?;
+
+        let body = res.text().await?;
+        let response: serde_json::Value = serde_json::from_str(&body).map_err(|_| PasteError::InvalidResponse(body.trim().to_string()))?;
+        let url = response.pointer(&self.url_pointer)
+            .and_then(|url| url.as_str())
+            .ok_or_else(|| PasteError::InvalidResponse(format!("no URL at {} in {}", self.url_pointer, response)))?;
+        Url::parse(url).map(Into::into).map_err(|_| PasteError::InvalidResponse(url.to_string()))
+    }
+
+    // Whatever serves the pastes is up to the receiving end
+    async fn fetch(&self, _url: &Url) -> Result<Vec<u8>, PasteError> {
+        Err(PasteError::Unsupported("downloading pastes"))
+    }
+
+    fn fields(&self, opts: &UploadOptions) -> Vec<(&'static str, String)> {
+        vec![
+            ("lang", opts.lang.clone()),
+            ("filename", opts.filename.clone().unwrap_or_default()),
+        ]
+    }
+
+    // The receiving end gets our own names and is left to translate them
+    fn translate_lexer(&self, canonical: &str) -> Option<String> {
+        Some(canonical.to_string())
+    }
+}
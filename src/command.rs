@@ -0,0 +1,126 @@
+use crate::PasteError;
+use std::io::Read;
+
+/// Runs `command` through the shell and returns its output as a console transcript. Stdout and
+/// stderr share one pipe so they stay interleaved as they were printed, and the first line shows
+/// the command with its exit status.
+pub async fn run_command(command: &str) -> Result<Vec<u8>, PasteError> {
+    let (mut reader, writer) = std::io::pipe()?;
+
+    let mut shell = shell(command);
+    shell.stdin(std::process::Stdio::null())
+        .stdout(writer.try_clone()?)
+        .stderr(writer);
+    tracing::debug!("running {:?}", shell);
+    let mut child = shell.spawn()?;
+    // The command holds the write ends too, and reading only ends once every one is closed
+    drop(shell);
+
+    let output = tokio::task::spawn_blocking(move || {
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output).map(|_| output)
+    });
+    let status = child.wait().await?;
+    let output = output.await.map_err(std::io::Error::other)??;
+
+    let mut transcript = format!("$ {}  ({})\n", command, status).into_bytes();
+    transcript.extend(output);
+    Ok(transcript)
+}
+
+/// How long `run_detector` waits for the detector to answer.
+pub const DETECTOR_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Bytes of the content a detector gets to see.
+pub const DETECTOR_HEAD: usize = 4096;
+
+/// Asks the user's `detector_command` for the lexer of an upload. It runs through the shell with
+/// the file name added as its last argument, none for stdin, and the start of the content on
+/// its stdin. Anything but a supported lexer on its first line of output, a failure or taking
+/// longer than `DETECTOR_TIMEOUT` gives `None`, leaving the built-in detection to it.
+pub fn run_detector(command: &str, filename: Option<&str>, content: &[u8]) -> Option<String> {
+    use std::io::Write;
+
+    let mut shell = detector_shell(command, filename);
+    shell.stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::inherit());
+    tracing::debug!("running detector {:?}", shell);
+    let mut child = shell.spawn().map_err(|e| tracing::warn!("could not run the detector: {}", e)).ok()?;
+
+    // Detectors that don't read their input shouldn't make writing it block or fail
+    let head = content[..content.len().min(DETECTOR_HEAD)].to_vec();
+    let mut stdin = child.stdin.take()?;
+    std::thread::spawn(move || {
+        let _ = stdin.write_all(&head);
+    });
+
+    let deadline = std::time::Instant::now() + DETECTOR_TIMEOUT;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if std::time::Instant::now() < deadline => std::thread::sleep(std::time::Duration::from_millis(10)),
+            Ok(None) => {
+                tracing::warn!("the detector took longer than {:?}, detecting the language without it", DETECTOR_TIMEOUT);
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+            Err(e) => {
+                tracing::warn!("could not wait for the detector: {}", e);
+                return None;
+            }
+        }
+    };
+
+    let mut output = String::new();
+    std::io::Read::read_to_string(&mut child.stdout.take()?, &mut output).ok()?;
+    if !status.success() {
+        tracing::debug!("detector exited with {}", status);
+        return None;
+    }
+    let lang = output.lines().next().unwrap_or_default().trim();
+    if lang.is_empty() {
+        return None;
+    }
+    if !crate::SUPPORTED_LANG.contains(&lang) {
+        tracing::warn!("the detector answered with unsupported language `{}`, ignoring it", lang);
+        return None;
+    }
+    tracing::debug!("detector picked {}", lang);
+    Some(lang.to_string())
+}
+
+// Unlike --cmd, always a POSIX shell, which gets the file name as `$1` so it needs no quoting.
+// The argument after the script becomes `$0`.
+#[cfg(unix)]
+fn detector_shell(command: &str, filename: Option<&str>) -> std::process::Command {
+    let mut cmd = std::process::Command::new("/bin/sh");
+    cmd.arg("-c").arg(format!("{} \"$@\"", command)).arg("paste").args(filename);
+    cmd
+}
+
+#[cfg(windows)]
+fn detector_shell(command: &str, filename: Option<&str>) -> std::process::Command {
+    let mut cmd = std::process::Command::new("cmd");
+    match filename {
+        Some(filename) => cmd.arg("/C").arg(format!("{} \"{}\"", command, filename)),
+        None => cmd.arg("/C").arg(command),
+    };
+    cmd
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> tokio::process::Command {
+    let shell = std::env::var("SHELL").ok().filter(|shell| !shell.is_empty()).unwrap_or("/bin/sh".to_string());
+    let mut cmd = tokio::process::Command::new(shell);
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> tokio::process::Command {
+    let mut cmd = tokio::process::Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
@@ -0,0 +1,256 @@
+/// Removes the comments from `text` for the lexer `lang`, keeping strings that look like they
+/// contain one. Lines left empty by it are dropped. `None` means there are no rules for the
+/// language, so nothing could be removed safely.
+pub fn strip_comments(lang: &str, text: &str) -> Option<String> {
+    let (_, rules) = RULES.iter().find(|(langs, _)| langs.contains(&lang))?;
+    Some(Stripper { rules, text, at: 0, out: String::with_capacity(text.len()), line: String::new(), stripped: false, heredocs: Vec::new() }.run())
+}
+
+// What tells a language's comments apart from its code
+struct Rules {
+    line: &'static str,
+    block: Option<(&'static str, &'static str)>,
+    nested_blocks: bool,
+    /// Delimiters of string literals, longest first, and whether a backslash escapes in them
+    strings: &'static [(&'static str, bool)],
+    /// Rust's `r#"..."#` strings and `'a'` chars, as opposed to its `'a` lifetimes
+    rust: bool,
+    /// C++'s `R"(...)"` strings and `1'000` digit separators
+    cpp: bool,
+    /// JavaScript's regex literals
+    regex: bool,
+    /// `#` only starts a comment at the start of a word, and heredocs are copied as they are
+    shell: bool,
+}
+
+const C_BASE: Rules = Rules { line: "//", block: Some(("/*", "*/")), nested_blocks: false, strings: &[("\"", true), ("'", true)], rust: false, cpp: false, regex: false, shell: false };
+
+const RULES: &[(&[&str], Rules)] = &[
+    (&["c", "objective-c"], C_BASE),
+    (&["cpp", "cuda", "arduino"], Rules { cpp: true, ..C_BASE }),
+    (&["java", "kotlin", "swift", "dart", "csharp", "sol"], Rules { strings: &[("\"\"\"", true), ("'''", true), ("\"", true), ("'", true)], ..C_BASE }),
+    (&["js", "jsx"], Rules { strings: &[("\"", true), ("'", true), ("`", true)], regex: true, ..C_BASE }),
+    (&["go"], Rules { strings: &[("\"", true), ("'", true), ("`", false)], ..C_BASE }),
+    (&["rust"], Rules { nested_blocks: true, strings: &[("\"", true)], rust: true, ..C_BASE }),
+    (&["python", "numpy"], Rules { line: "#", block: None, strings: &[("\"\"\"", true), ("'''", true), ("\"", true), ("'", true)], ..C_BASE }),
+    (&["bash"], Rules { line: "#", block: None, strings: &[("\"", true), ("'", false), ("`", true)], shell: true, ..C_BASE }),
+];
+
+struct Stripper<'a> {
+    rules: &'a Rules,
+    text: &'a str,
+    at: usize,
+    out: String,
+    /// The current line, which is dropped if removing a comment leaves it blank
+    line: String,
+    stripped: bool,
+    /// Terminators of the heredocs started on the current line, and whether tabs may indent them
+    heredocs: Vec<(String, bool)>,
+}
+
+impl Stripper<'_> {
+    fn run(mut self) -> String {
+        if self.rules.line == "#" && self.text.starts_with("#!") {
+            let end = self.text.find('\n').map_or(self.text.len(), |i| i + 1);
+            self.out.push_str(&self.text[..end]);
+            self.at = end;
+        }
+
+        while let Some(c) = self.text[self.at..].chars().next() {
+            let rest = &self.text[self.at..];
+            if c == '\n' {
+                self.at += 1;
+                self.end_line(true);
+                self.copy_heredocs();
+            } else if let Some(len) = self.comment(rest) {
+                self.at += len;
+                self.stripped = true;
+                // The comment kept `a/**/b` apart
+                let next = self.text[self.at..].chars().next();
+                if self.line.ends_with(|c: char| !c.is_whitespace()) && next.is_some_and(|c| !c.is_whitespace()) {
+                    self.line.push(' ');
+                }
+            } else if let Some(len) = self.literal(rest) {
+                self.line.push_str(&rest[..len]);
+                self.at += len;
+            } else {
+                // An escaped `#` or quote is code, not the start of anything
+                let len = match (c, rest[1..].chars().next()) {
+                    ('\\', Some(next)) if self.rules.shell => 1 + next.len_utf8(),
+                    _ => c.len_utf8(),
+                };
+                self.line.push_str(&rest[..len]);
+                self.at += len;
+            }
+        }
+        self.end_line(false);
+        self.out
+    }
+
+    fn end_line(&mut self, newline: bool) {
+        if self.stripped {
+            self.line.truncate(self.line.trim_end().len());
+            self.stripped = false;
+            if self.line.is_empty() {
+                return;
+            }
+        }
+        self.out.push_str(&self.line);
+        if newline {
+            self.out.push('\n');
+        }
+        self.line.clear();
+    }
+
+    fn copy_heredocs(&mut self) {
+        for (word, tabs) in std::mem::take(&mut self.heredocs) {
+            while self.at < self.text.len() {
+                let rest = &self.text[self.at..];
+                let end = rest.find('\n').map_or(rest.len(), |i| i + 1);
+                let line = &rest[..end];
+                self.out.push_str(line);
+                self.at += end;
+                let line = line.trim_end_matches(['\n', '\r']);
+                if (if tabs { line.trim_start_matches('\t') } else { line }) == word {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Length of the comment `rest` starts with
+    fn comment(&self, rest: &str) -> Option<usize> {
+        if rest.starts_with(self.rules.line) {
+            if self.rules.shell && !self.line.is_empty() && !self.line.ends_with(|c: char| c.is_whitespace() || ";|&()".contains(c)) {
+                return None;
+            }
+            return Some(rest.find('\n').unwrap_or(rest.len()));
+        }
+
+        let (open, close) = self.rules.block?;
+        if !rest.starts_with(open) {
+            return None;
+        }
+        let (mut i, mut depth) = (open.len(), 1);
+        while i < rest.len() {
+            if rest[i..].starts_with(close) {
+                i += close.len();
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            } else if self.rules.nested_blocks && rest[i..].starts_with(open) {
+                i += open.len();
+                depth += 1;
+            } else {
+                i += rest[i..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        Some(rest.len())
+    }
+
+    // Length of the string, char or regex literal `rest` starts with
+    fn literal(&mut self, rest: &str) -> Option<usize> {
+        let prev = self.line.chars().next_back();
+        let after_word = prev.is_some_and(|c| c.is_alphanumeric() || c == '_');
+
+        if self.rules.rust && !after_word {
+            if let Some(raw) = rest.strip_prefix("br").or_else(|| rest.strip_prefix('r')) {
+                let hashes = raw.len() - raw.trim_start_matches('#').len();
+                let open = rest.len() - raw.len() + hashes;
+                if rest[open..].starts_with('"') {
+                    let close = format!("\"{}", "#".repeat(hashes));
+                    return Some(rest[open + 1..].find(&close).map_or(rest.len(), |i| open + 1 + i + close.len()));
+                }
+            }
+        }
+        if self.rules.rust && rest.starts_with('\'') {
+            let mut chars = rest.char_indices().skip(1);
+            return match (chars.next(), chars.next()) {
+                (Some((_, '\\')), _) => Some(scan_string(rest, "'", true)),
+                (Some(_), Some((i, '\''))) => Some(i + 1),
+                // A lifetime or a label
+                _ => None,
+            };
+        }
+        if self.rules.cpp && rest.starts_with("R\"") && !after_word {
+            if let Some(paren) = rest[2..].find('(') {
+                let close = format!("){}\"", &rest[2..2 + paren]);
+                let body = 2 + paren + 1;
+                return Some(rest[body..].find(&close).map_or(rest.len(), |i| body + i + close.len()));
+            }
+        }
+        if self.rules.cpp && rest.starts_with('\'') && prev.is_some_and(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        if self.rules.regex && rest.starts_with('/') && self.regex_may_start() {
+            return scan_regex(rest);
+        }
+        if self.rules.shell && rest.starts_with("<<") && !rest.starts_with("<<<") {
+            return Some(self.heredoc(rest));
+        }
+
+        let &(delimiter, escapes) = self.rules.strings.iter().find(|(delimiter, _)| rest.starts_with(delimiter))?;
+        Some(scan_string(rest, delimiter, escapes))
+    }
+
+    // A slash after an operator or at the start of a statement can't be a division
+    fn regex_may_start(&self) -> bool {
+        let code = self.line.trim_end();
+        code.is_empty() || code.ends_with(|c: char| "(,=:[!&|?{};+-*%<>~^".contains(c)) || code.ends_with("return") || code.ends_with("typeof")
+    }
+
+    // Reads the `<<-'EOF'` that starts a heredoc, whose body follows the current line
+    fn heredoc(&mut self, rest: &str) -> usize {
+        let mut i = 2;
+        let tabs = rest[i..].starts_with('-');
+        if tabs {
+            i += 1;
+        }
+        i += rest[i..].len() - rest[i..].trim_start_matches([' ', '\t']).len();
+        let quote = rest[i..].chars().next().filter(|c| *c == '\'' || *c == '"');
+        i += quote.map_or(0, char::len_utf8);
+        let word_len = rest[i..].find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len() - i);
+        let word = &rest[i..i + word_len];
+        i += word_len;
+        // `1<<2` in arithmetic is no heredoc
+        if word.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+            self.heredocs.push((word.to_string(), tabs));
+            i += quote.filter(|quote| rest[i..].starts_with(*quote)).map_or(0, char::len_utf8);
+        }
+        i
+    }
+}
+
+// Length of the string that `rest` starts with, up to and including its closing delimiter. An
+// unterminated string runs to the end, as it would for the compiler.
+fn scan_string(rest: &str, delimiter: &str, escapes: bool) -> usize {
+    let mut chars = rest.char_indices().skip(delimiter.chars().count());
+    while let Some((i, c)) = chars.next() {
+        if escapes && c == '\\' {
+            chars.next();
+        } else if rest[i..].starts_with(delimiter) {
+            return i + delimiter.len();
+        }
+    }
+    rest.len()
+}
+
+// A regex ends on its line, and a slash in a `[...]` class doesn't end it
+fn scan_regex(rest: &str) -> Option<usize> {
+    let mut class = false;
+    let mut chars = rest.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '\n' => return None,
+            '[' => class = true,
+            ']' => class = false,
+            '/' if !class => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
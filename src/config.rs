@@ -0,0 +1,84 @@
+use crate::{ExpireSpec, PasteError, SUPPORTED_LANG};
+use std::path::{Path, PathBuf};
+
+/// Defaults read from `config.toml` in the zero-paste config directory. Every key is optional.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub default_expire: Option<ExpireSpec>,
+    /// Lexer for content no language is detected for
+    pub default_lang: Option<String>,
+    /// Name of the backend, as accepted by `--backend`
+    pub backend: Option<String>,
+    /// Backends to try in turn, the first that succeeds wins. Takes precedence over `backend`
+    pub backends: Option<Vec<String>>,
+    pub base_url: Option<String>,
+    /// `User-Agent` for every request, see `--user-agent`
+    pub user_agent: Option<String>,
+    /// Shell command that picks the lexer for an upload, see `run_detector`
+    pub detector_command: Option<String>,
+    /// Endpoint of the webhook backend
+    pub webhook: Option<WebhookConfig>,
+    /// Keys of the pastebin backend
+    pub pastebin: Option<PastebinConfig>,
+}
+
+/// The `[webhook]` table of the config file.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    /// Where the paste is POSTed as JSON
+    pub url: Option<String>,
+    /// JSON pointer to the paste URL in the response, `/url` by default
+    pub url_pointer: Option<String>,
+}
+
+/// The `[pastebin]` table of the config file. `$PASTEBIN_API_KEY` and `$PASTEBIN_USER_KEY` take
+/// precedence over it.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PastebinConfig {
+    /// Developer key from https://pastebin.com/doc_api
+    pub api_key: Option<String>,
+    /// Key of the account to create pastes in, from the API's login call; private pastes need it
+    pub user_key: Option<String>,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        crate::config_dir().map(|dir| dir.join("config.toml"))
+    }
+
+    /// Loads the user's config file. A missing file just means the built-in defaults apply.
+    pub fn load() -> Result<Config, PasteError> {
+        match Config::path() {
+            Some(path) => Config::load_from(&path),
+            None => Ok(Config::default()),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Config, PasteError> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let invalid = |reason: String| PasteError::InvalidConfig { path: path.to_path_buf(), reason };
+        let config: Config = toml::from_str(&content).map_err(|e| invalid(e.message().to_string()))?;
+        if let Some(lang) = &config.default_lang {
+            if !SUPPORTED_LANG.contains(&lang.as_str()) {
+                return Err(invalid(format!("unsupported default_lang `{}`", lang)));
+            }
+        }
+        if let Some(webhook) = &config.webhook {
+            if let Some(url) = &webhook.url {
+                reqwest::Url::parse(url).map_err(|e| invalid(format!("invalid webhook.url `{}`: {}", url, e)))?;
+            }
+            if let Some(pointer) = webhook.url_pointer.as_deref().filter(|pointer| !pointer.is_empty() && !pointer.starts_with('/')) {
+                return Err(invalid(format!("invalid webhook.url_pointer `{}`, expected a JSON pointer such as /data/url", pointer)));
+            }
+        }
+        Ok(config)
+    }
+}
@@ -0,0 +1,130 @@
+use crate::PasteError;
+use std::path::Path;
+
+/// Encrypts `content` to an age or SSH public key. The result is ASCII-armored, so text-only
+/// services take it too.
+pub fn encrypt(content: &[u8], recipient: &str) -> Result<Vec<u8>, PasteError> {
+    let invalid = || PasteError::InvalidRecipient(recipient.to_string());
+    let armored = if recipient.starts_with("age1") {
+        let recipient: age::x25519::Recipient = recipient.parse().map_err(|_| invalid())?;
+        age::encrypt_and_armor(&recipient, content)
+    } else {
+        let recipient: age::ssh::Recipient = recipient.parse().map_err(|_| invalid())?;
+        age::encrypt_and_armor(&recipient, content)
+    };
+    Ok(armored.map_err(|e| PasteError::Encryption(e.to_string()))?.into_bytes())
+}
+
+/// Decrypts content from `encrypt` with the key in `identity`, an age identity file or an
+/// unencrypted SSH private key. The plaintext is only ever held in memory.
+pub fn decrypt(ciphertext: &[u8], identity: &Path) -> Result<Vec<u8>, PasteError> {
+    use std::io::Read;
+
+    let failed = |e: &dyn std::fmt::Display| PasteError::Decryption(e.to_string());
+    let key = std::fs::read(identity)?;
+
+    let identities: Vec<Box<dyn age::Identity>> = match age::IdentityFile::from_buffer(&key[..]) {
+        Ok(file) => file.into_identities().map_err(|e| failed(&e))?
+            .into_iter()
+            .map(|identity| identity as Box<dyn age::Identity>)
+            .collect(),
+        Err(_) => {
+            let filename = identity.to_str().map(str::to_string);
+            match age::ssh::Identity::from_buffer(&key[..], filename).map_err(|e| failed(&e))? {
+                age::ssh::Identity::Encrypted(_) => return Err(failed(&"passphrase-protected SSH keys are not supported")),
+                age::ssh::Identity::Unsupported(key) => return Err(failed(&format!("unsupported SSH key {:?}", key))),
+                identity => vec![Box::new(identity)],
+            }
+        }
+    };
+
+    let decryptor = age::Decryptor::new_buffered(age::armor::ArmoredReader::new(ciphertext)).map_err(|e| failed(&e))?;
+    let mut reader = decryptor.decrypt(identities.iter().map(|identity| identity.as_ref())).map_err(|e| failed(&e))?;
+    let mut plaintext = Vec::new();
+    reader.read_to_end(&mut plaintext)?;
+    Ok(plaintext)
+}
+
+/// How a paste is encrypted before it's uploaded.
+#[derive(Debug, Clone)]
+pub enum Encryption {
+    /// To an age or SSH public key
+    Recipient(String),
+    /// With a key derived from a password
+    Password(String),
+}
+
+impl Encryption {
+    pub fn encrypt(&self, content: &[u8]) -> Result<Vec<u8>, PasteError> {
+        match self {
+            Encryption::Recipient(recipient) => encrypt(content, recipient),
+            Encryption::Password(password) => encrypt_with_password(content, password),
+        }
+    }
+}
+
+// Starts content from `encrypt_with_password`, followed by base64 of salt, nonce and ciphertext
+const PASSWORD_HEADER: &str = "zero-paste-encrypted:v1:";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Encrypts `content` with AES-256-GCM under a key derived from `password` with Argon2.
+/// The salt and nonce travel with the ciphertext, but the password never leaves the machine.
+pub fn encrypt_with_password(content: &[u8], password: &str) -> Result<Vec<u8>, PasteError> {
+    use aes_gcm::aead::Aead;
+    use base64::Engine;
+
+    let failed = |e: &dyn std::fmt::Display| PasteError::Encryption(e.to_string());
+    let mut salt = [0; SALT_LEN];
+    let mut nonce = [0; NONCE_LEN];
+    getrandom::fill(&mut salt).and_then(|_| getrandom::fill(&mut nonce)).map_err(|e| failed(&e))?;
+
+    let cipher = password_cipher(password, &salt).map_err(|e| failed(&e))?;
+    let ciphertext = cipher.encrypt(&nonce.into(), content).map_err(|e| failed(&e))?;
+
+    let blob = [&salt[..], &nonce, &ciphertext].concat();
+    Ok(format!("{}{}\n", PASSWORD_HEADER, base64::engine::general_purpose::STANDARD.encode(blob)).into_bytes())
+}
+
+/// Reverses `encrypt_with_password`. A wrong password fails rather than returning garbage.
+pub fn decrypt_with_password(content: &[u8], password: &str) -> Result<Vec<u8>, PasteError> {
+    use aes_gcm::aead::Aead;
+    use base64::Engine;
+
+    let failed = |e: &dyn std::fmt::Display| PasteError::Decryption(e.to_string());
+    let text = std::str::from_utf8(content).ok()
+        .and_then(|text| text.trim().strip_prefix(PASSWORD_HEADER))
+        .ok_or_else(|| failed(&"not a password-encrypted paste"))?;
+    let blob = base64::engine::general_purpose::STANDARD.decode(text).map_err(|e| failed(&e))?;
+    if blob.len() < SALT_LEN + NONCE_LEN {
+        return Err(failed(&"the paste is truncated"));
+    }
+
+    let (salt, rest) = blob.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let nonce: [u8; NONCE_LEN] = nonce.try_into().unwrap();
+    let cipher = password_cipher(password, salt).map_err(|e| failed(&e))?;
+    cipher.decrypt(&nonce.into(), ciphertext).map_err(|_| failed(&"wrong password or corrupted paste"))
+}
+
+/// Whether `content` came from `encrypt_with_password`.
+pub fn is_password_encrypted(content: &[u8]) -> bool {
+    content.starts_with(PASSWORD_HEADER.as_bytes())
+}
+
+/// A random password suitable for putting in a URL fragment.
+pub fn generate_password() -> Result<String, PasteError> {
+    use base64::Engine;
+
+    let mut key = [0; 32];
+    getrandom::fill(&mut key).map_err(|e| PasteError::Encryption(e.to_string()))?;
+    Ok(base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(key))
+}
+
+fn password_cipher(password: &str, salt: &[u8]) -> Result<aes_gcm::Aes256Gcm, argon2::Error> {
+    use aes_gcm::KeyInit;
+
+    let mut key = [0; 32];
+    argon2::Argon2::default().hash_password_into(password.as_bytes(), salt, &mut key)?;
+    Ok(aes_gcm::Aes256Gcm::new(&key.into()))
+}
@@ -0,0 +1,86 @@
+#[derive(Debug, thiserror::Error)]
+pub enum PasteError {
+    #[error("unsupported language: {0}")]
+    UnsupportedLang(String),
+    #[error("unsupported expire time: {0}")]
+    UnsupportedExpire(String),
+    #[error("the paste service rejected the upload ({status}): {reason}")]
+    UploadRejected { status: reqwest::StatusCode, reason: String },
+    #[error("the paste service is rate limiting uploads{}; wait a while or lower --concurrency", retry_in(.retry_after))]
+    RateLimited { retry_after: Option<std::time::Duration> },
+    #[error("stdin is a terminal; pipe the content to paste into `paste -`")]
+    StdinIsTerminal,
+    #[error("could not find CSRF token; the paste service may have changed")]
+    CsrfTokenMissing,
+    #[error("file is not valid UTF-8 text; use a binary-capable backend or --force")]
+    NotUtf8,
+    #[error("content looks like {0}, not text; use a binary-capable backend such as 0x0, or --force")]
+    BinaryContent(String),
+    #[error("{} is a directory, which is uploaded as an archive and needs a binary-capable backend such as 0x0 or --base64", .0.display())]
+    ArchiveNeedsBinary(std::path::PathBuf),
+    #[error("{url} is larger than the {limit} byte limit; pass --yes or raise --max-size")]
+    RemoteTooLarge { url: reqwest::Url, limit: u64 },
+    #[error("invalid proxy URL '{proxy}': {reason}")]
+    InvalidProxy { proxy: String, reason: String },
+    #[error("invalid header '{header}': {reason}")]
+    InvalidHeader { header: String, reason: String },
+    #[error("invalid config file {}: {reason}", path.display())]
+    InvalidConfig { path: std::path::PathBuf, reason: String },
+    #[error("invalid base URL {0}, expected an http(s) URL")]
+    InvalidBaseUrl(reqwest::Url),
+    #[error("{0} is not set")]
+    MissingEnv(&'static str),
+    #[error("{0} is not set in the config file")]
+    MissingConfig(&'static str),
+    #[error("{what} is needed; set ${env} or {config} in the config file")]
+    MissingCredential { what: &'static str, env: &'static str, config: &'static str },
+    #[error("the {0} backend has no public instance; pass --base-url or set base_url in the config file")]
+    MissingBaseUrl(&'static str),
+    #[error("{0} is not supported by this paste service")]
+    Unsupported(&'static str),
+    #[error("no deletion token for {0}; only pastes uploaded from here with one in the history can be deleted")]
+    DeleteTokenMissing(reqwest::Url),
+    #[error("paste {0} was not found; it may have expired or been a one-time paste that was already viewed")]
+    NotFound(reqwest::Url),
+    #[error("lines {range} are out of range; the file has {lines} lines")]
+    LinesOutOfRange { range: crate::LineRange, lines: usize },
+    #[error("invalid recipient `{0}`, expected an age or SSH public key")]
+    InvalidRecipient(String),
+    #[error("could not encrypt the paste: {0}")]
+    Encryption(String),
+    #[error("could not decrypt the paste: {0}")]
+    Decryption(String),
+    #[error("the content is not valid {format}: {reason}; fix it or pass --force")]
+    InvalidSyntax { format: &'static str, reason: String },
+    #[error("the paste is not valid base64: {0}")]
+    InvalidBase64(String),
+    #[error("unexpected response from the paste service: {0}")]
+    InvalidResponse(String),
+    #[error("cannot read '{}': {}", path.display(), read_failure(source))]
+    ReadInput { path: std::path::PathBuf, #[source] source: std::io::Error },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+}
+
+impl PasteError {
+    // For `map_err` on reading an input, so the message names the file
+    pub(crate) fn reading(path: impl AsRef<std::path::Path>) -> impl FnOnce(std::io::Error) -> PasteError {
+        let path = path.as_ref().to_path_buf();
+        move |source| PasteError::ReadInput { path, source }
+    }
+}
+
+fn retry_in(retry_after: &Option<std::time::Duration>) -> String {
+    retry_after.map_or(String::new(), |after| format!(" for the next {}", humantime::format_duration(after)))
+}
+
+fn read_failure(source: &std::io::Error) -> String {
+    match source.kind() {
+        std::io::ErrorKind::NotFound => "no such file or directory".to_string(),
+        std::io::ErrorKind::PermissionDenied => "permission denied; check that you can read it".to_string(),
+        std::io::ErrorKind::IsADirectory => "it's a directory, which can only be uploaded as a .tar.gz archive".to_string(),
+        _ => source.to_string(),
+    }
+}
@@ -0,0 +1,168 @@
+use crate::PasteError;
+
+pub const SUPPORTED_EXPIRE: [&str; 5] = ["once", "1h", "1d", "1w", "21d"];
+
+/// The expire presets paste.mozilla.org offers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Expire {
+    #[default]
+    Once,
+    Hour,
+    Day,
+    Week,
+    ThreeWeeks,
+}
+
+impl Expire {
+    const TIMED: [Expire; 4] = [Expire::Hour, Expire::Day, Expire::Week, Expire::ThreeWeeks];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Expire::Once => "once",
+            Expire::Hour => "1h",
+            Expire::Day => "1d",
+            Expire::Week => "1w",
+            Expire::ThreeWeeks => "21d",
+        }
+    }
+
+    /// The preset closest to `spec`, which may be any duration.
+    pub fn nearest(spec: ExpireSpec) -> Expire {
+        match spec {
+            ExpireSpec::Once => Expire::Once,
+            ExpireSpec::Seconds(secs) => Expire::TIMED.into_iter()
+                .min_by_key(|preset| ExpireSpec::from(*preset).seconds().unwrap_or_default().abs_diff(secs))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl std::str::FromStr for Expire {
+    type Err = PasteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "once" => Ok(Expire::Once),
+            "1h" => Ok(Expire::Hour),
+            "1d" => Ok(Expire::Day),
+            "1w" => Ok(Expire::Week),
+            "21d" => Ok(Expire::ThreeWeeks),
+            _ => Err(PasteError::UnsupportedExpire(s.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for Expire {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An arbitrary expire time: one-time, or a lifetime in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(try_from = "String")]
+pub enum ExpireSpec {
+    #[default]
+    Once,
+    Seconds(u64),
+}
+
+impl ExpireSpec {
+    pub fn seconds(&self) -> Option<u64> {
+        match self {
+            ExpireSpec::Once => None,
+            ExpireSpec::Seconds(secs) => Some(*secs),
+        }
+    }
+}
+
+impl From<Expire> for ExpireSpec {
+    fn from(expire: Expire) -> Self {
+        match expire {
+            Expire::Once => ExpireSpec::Once,
+            Expire::Hour => ExpireSpec::Seconds(HOUR),
+            Expire::Day => ExpireSpec::Seconds(DAY),
+            Expire::Week => ExpireSpec::Seconds(WEEK),
+            Expire::ThreeWeeks => ExpireSpec::Seconds(3 * WEEK),
+        }
+    }
+}
+
+impl std::str::FromStr for ExpireSpec {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_expire(s)
+    }
+}
+
+impl TryFrom<String> for ExpireSpec {
+    type Error = ParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        parse_expire(&s)
+    }
+}
+
+impl std::fmt::Display for ExpireSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ExpireSpec::Seconds(secs) = *self else {
+            return f.write_str("once");
+        };
+        // Use the largest unit the duration is a whole multiple of
+        let (unit, size) = UNITS.into_iter()
+            .rev()
+            .find(|(_, size)| secs % size == 0)
+            .unwrap_or(UNITS[0]);
+        write!(f, "{}{}", secs / size, unit)
+    }
+}
+
+/// Writes the expire time of an uploaded paste, `never` for one the service keeps for as long as
+/// it likes.
+pub fn format_expiry(expires: Option<ExpireSpec>) -> String {
+    expires.map_or_else(|| NEVER.to_string(), |expires| expires.to_string())
+}
+
+/// Reads what `format_expiry` wrote.
+pub fn parse_expiry(s: &str) -> Result<Option<ExpireSpec>, ParseError> {
+    match s {
+        NEVER => Ok(None),
+        _ => parse_expire(s).map(Some),
+    }
+}
+
+const NEVER: &str = "never";
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid expire time '{0}', expected `once` or a duration such as 30m, 12h or 3d")]
+pub struct ParseError(String);
+
+impl From<ParseError> for PasteError {
+    fn from(e: ParseError) -> Self {
+        PasteError::UnsupportedExpire(e.0)
+    }
+}
+
+const MINUTE: u64 = 60;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+const UNITS: [(char, u64); 5] = [('s', 1), ('m', MINUTE), ('h', HOUR), ('d', DAY), ('w', WEEK)];
+
+/// Parses `once` or a positive duration with one of the `s`, `m`, `h`, `d` or `w` suffixes.
+pub fn parse_expire(s: &str) -> Result<ExpireSpec, ParseError> {
+    if s == "once" {
+        return Ok(ExpireSpec::Once);
+    }
+
+    let invalid = || ParseError(s.to_string());
+    let suffix = s.chars().last().ok_or_else(invalid)?;
+    let (_, size) = UNITS.into_iter().find(|(unit, _)| *unit == suffix).ok_or_else(invalid)?;
+    let count: u64 = s[..s.len() - 1].parse().map_err(|_| invalid())?;
+
+    match count.checked_mul(size) {
+        Some(secs) if secs > 0 => Ok(ExpireSpec::Seconds(secs)),
+        _ => Err(invalid()),
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{ExpireSpec, PasteError, PasteResult};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// A successful upload, as recorded in `history.jsonl` in the zero-paste data directory.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct HistoryEntry {
+    /// Time of the upload in RFC 3339 format
+    pub timestamp: String,
+    /// File as given on the command line, `None` for stdin
+    pub file: Option<String>,
+    pub lang: String,
+    /// Expire time the service applied, `never` if it keeps the paste for as long as it likes
+    pub expires: String,
+    /// Expire time asked for, which `expires` may have been rounded from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requested_expire: Option<String>,
+    pub url: String,
+    /// Name of the backend, as accepted by `--backend`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delete_token: Option<String>,
+    /// Hex SHA-256 of the uploaded content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Length of the uploaded content in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+}
+
+impl HistoryEntry {
+    pub fn new(file: &str, backend: &str, requested_expire: ExpireSpec, paste: &PasteResult) -> Self {
+        HistoryEntry {
+            timestamp: humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string(),
+            file: (file != "-").then(|| file.to_string()),
+            lang: paste.lang.clone(),
+            expires: crate::format_expiry(paste.expires),
+            requested_expire: Some(requested_expire.to_string()),
+            url: paste.url.to_string(),
+            backend: Some(backend.to_string()),
+            delete_token: paste.delete_token.clone(),
+            sha256: Some(paste.sha256.clone()),
+            bytes: Some(paste.bytes),
+        }
+    }
+
+    /// When the paste is due to expire, `None` for one-time pastes, pastes without an expire time
+    /// or unreadable entries.
+    pub fn expires_at(&self) -> Option<std::time::SystemTime> {
+        let expire = crate::parse_expiry(&self.expires).ok()??;
+        let uploaded = humantime::parse_rfc3339(&self.timestamp).ok()?;
+        Some(uploaded + std::time::Duration::from_secs(expire.seconds()?))
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("zero-paste").join("history.jsonl"))
+    }
+
+    /// Appends the entry to the user's history file, creating it on first use.
+    pub fn append(&self) -> Result<(), PasteError> {
+        match HistoryEntry::path() {
+            Some(path) => self.append_to(&path),
+            None => Ok(()),
+        }
+    }
+
+    pub fn append_to(&self, path: &Path) -> Result<(), PasteError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut line = serde_json::to_vec(self).map_err(std::io::Error::other)?;
+        line.push(b'\n');
+
+        // A single write in append mode keeps lines from concurrent runs from interleaving
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(&line)?;
+        Ok(())
+    }
+
+    /// Reads the user's history, oldest entry first. No history file yet means no entries.
+    pub fn load() -> Result<Vec<HistoryEntry>, PasteError> {
+        match HistoryEntry::path() {
+            Some(path) => HistoryEntry::load_from(&path),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Finds the latest entry for a paste URL or deletion token.
+    pub fn find<'a>(entries: &'a [HistoryEntry], url_or_token: &str) -> Option<&'a HistoryEntry> {
+        entries.iter().rev().find(|entry| entry.url == url_or_token || entry.delete_token.as_deref() == Some(url_or_token))
+    }
+
+    /// Finds an earlier paste of the same content on `backend`, uploaded asking for the same expire
+    /// time, that should still be up. One-time pastes never match, since whoever viewed them first
+    /// used them up.
+    pub fn find_duplicate<'a>(entries: &'a [HistoryEntry], sha256: &str, backend: &str, expire: ExpireSpec) -> Option<&'a HistoryEntry> {
+        let now = std::time::SystemTime::now();
+        entries.iter().rev().find(|entry| {
+            // Entries from before `requested_expire` only have the one asked for
+            let requested = entry.requested_expire.as_deref().unwrap_or(&entry.expires);
+            let up = match crate::parse_expiry(&entry.expires) {
+                Ok(None) => true,
+                _ => entry.expires_at().is_some_and(|at| at > now),
+            };
+            entry.sha256.as_deref() == Some(sha256)
+                && entry.backend.as_deref() == Some(backend)
+                && requested.parse().ok() == Some(expire)
+                && up
+        })
+    }
+
+    pub fn load_from(path: &Path) -> Result<Vec<HistoryEntry>, PasteError> {
+        let file = match std::fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut entries = Vec::new();
+        for (i, line) in std::io::BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // One bad line, say from a run killed mid-write, shouldn't hide the rest
+            match serde_json::from_str(&line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => tracing::warn!("ignoring line {} of {}: {}", i + 1, path.display(), e),
+            }
+        }
+        Ok(entries)
+    }
+}
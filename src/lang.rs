@@ -0,0 +1,481 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+pub const SUPPORTED_LANG: [&str; 63] = ["_text", "_markdown", "_rst", "_code", "applescript", "arduino", "bash", "bat", "c", "clojure", "cmake", "coffee-script", "common-lisp", "console", "cpp", "csharp", "css", "cuda", "dart", "delphi", "diff", "django", "dker", "elixir", "erlang", "go", "handlebars", "haskell", "html", "html+django", "ini", "ipythonconsole", "irc", "java", "js", "json", "jsx", "kotlin", "less", "lua", "make", "matlab", "nginx", "numpy", "objective-c", "perl", "php", "postgresql", "python", "rb", "rst", "rust", "sass", "scss", "sol", "sql", "swift", "tex", "typoscript", "vim", "xml", "xslt", "yaml"];
+
+// Whole file names that don't follow the regular file extension pattern
+const SPECIAL_FILENAMES: &[(&str, &str)] = &[
+    ("dockerfile", "dker"),
+    ("makefile", "make"),
+    ("cmakelists.txt", "cmake"),
+    ("nginx.conf", "nginx"),
+];
+
+// Extensions of several parts that mean something other than their last part
+const COMPOUND_EXTENSIONS: &[(&str, &str)] = &[
+    ("php.inc", "php"),
+    ("d.ts", "js"),
+];
+
+// Template extensions wrapped around another format, like nginx.conf.j2 or config.h.in
+const TEMPLATE_WRAPPERS: &[&str] = &["j2", "jinja", "jinja2", "tmpl", "tpl", "in"];
+
+// File extensions for each lexer
+const EXTENSIONS: &[(&str, &[&str])] = &[
+    ("_text", &["txt"]),
+    ("_markdown", &["md"]),
+    ("_rst", &["rst"]),
+    // Languages without a lexer of their own, which heuristics for others would only get wrong
+    ("_code", &["zig", "nim", "ps1", "r", "jl", "fs", "ml", "proto", "graphql", "tf"]),
+    ("bash", &["sh"]),
+    ("bat", &["bat"]),
+    ("c", &["c"]),
+    ("common-lisp", &["lisp", "lsp", "cl"]),
+    ("cpp", &["cpp", "cc", "cxx", "hpp", "hxx", "inc", "hh", "h"]),
+    ("csharp", &["cs"]),
+    ("cmake", &["cmake", "in"]),
+    ("css", &["css"]),
+    ("dart", &["dart"]),
+    ("diff", &["patch", "diff"]),
+    ("dker", &["dockerfile"]),
+    ("elixir", &["elixir", "ex", "exs"]),
+    ("erlang", &["erl"]),
+    ("go", &["go"]),
+    ("handlebars", &["hbs"]),
+    ("haskell", &["hs"]),
+    ("html", &["html", "htm", "shtm", "shtml", "vue", "svelte", "astro"]),
+    ("ini", &["ini", "toml"]),
+    ("java", &["java", "groovy", "scala"]),
+    ("js", &["js", "ts"]),
+    ("json", &["json", "jsonl"]),
+    ("jsx", &["tsx", "jsx"]),
+    ("kotlin", &["kt", "kts"]),
+    ("lua", &["lua"]),
+    ("objective-c", &["m", "mm"]),
+    ("perl", &["pl"]),
+    ("php", &["php"]),
+    ("python", &["py"]),
+    ("rb", &["rb"]),
+    ("rust", &["rs"]),
+    ("sass", &["sass"]),
+    ("scss", &["scss"]),
+    ("sol", &["sol"]),
+    ("sql", &["sql"]),
+    ("swift", &["swift"]),
+    ("tex", &["tex"]),
+    ("typoscript", &["typoscript"]),
+    ("vim", &["vim"]),
+    ("xml", &["xml"]),
+    ("xslt", &["xsl", "xslt"]),
+    ("yaml", &["yml", "yaml"]),
+];
+
+// Every lexer the tables refer to has to exist; a typo like `docker` for `dker` fails the build
+const _: () = {
+    let mut i = 0;
+    while i < EXTENSIONS.len() {
+        assert!(is_supported(EXTENSIONS[i].0), "EXTENSIONS refers to an unsupported lexer");
+        i += 1;
+    }
+    let mut i = 0;
+    while i < SPECIAL_FILENAMES.len() {
+        assert!(is_supported(SPECIAL_FILENAMES[i].1), "SPECIAL_FILENAMES refers to an unsupported lexer");
+        i += 1;
+    }
+    let mut i = 0;
+    while i < COMPOUND_EXTENSIONS.len() {
+        assert!(is_supported(COMPOUND_EXTENSIONS[i].1), "COMPOUND_EXTENSIONS refers to an unsupported lexer");
+        i += 1;
+    }
+    let mut i = 0;
+    while i < MIME_TYPES.len() {
+        assert!(is_supported(MIME_TYPES[i].1), "MIME_TYPES refers to an unsupported lexer");
+        i += 1;
+    }
+    let mut i = 0;
+    while i < LINE_COMMENTS.len() {
+        assert!(is_supported(LINE_COMMENTS[i].0), "LINE_COMMENTS refers to an unsupported lexer");
+        i += 1;
+    }
+};
+
+// `SUPPORTED_LANG.contains`, usable in constants
+const fn is_supported(lang: &str) -> bool {
+    let mut i = 0;
+    while i < SUPPORTED_LANG.len() {
+        let (a, b) = (SUPPORTED_LANG[i].as_bytes(), lang.as_bytes());
+        if a.len() == b.len() {
+            let mut j = 0;
+            while j < a.len() && a[j] == b[j] {
+                j += 1;
+            }
+            if j == a.len() {
+                return true;
+            }
+        }
+        i += 1;
+    }
+    false
+}
+
+// Lexers for MIME types guessed from extensions we don't know ourselves. text/plain is left out
+// so the content can still be looked at for plain-text files.
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("application/javascript", "js"),
+    ("text/javascript", "js"),
+    ("application/json", "json"),
+    ("application/xml", "xml"),
+    ("text/xml", "xml"),
+    ("text/html", "html"),
+    ("application/xhtml+xml", "html"),
+    ("text/markdown", "_markdown"),
+    ("text/css", "css"),
+    ("text/x-c", "c"),
+    ("text/x-csrc", "c"),
+    ("text/x-c++src", "cpp"),
+    ("text/x-java-source", "java"),
+    ("text/x-python", "python"),
+    ("application/x-sh", "bash"),
+    ("text/x-shellscript", "bash"),
+    ("application/x-perl", "perl"),
+    ("text/x-perl", "perl"),
+    ("application/x-httpd-php", "php"),
+    ("text/x-ruby", "rb"),
+    ("text/x-lua", "lua"),
+    ("application/sql", "sql"),
+    ("application/x-sql", "sql"),
+    ("application/yaml", "yaml"),
+    ("application/x-yaml", "yaml"),
+    ("text/x-yaml", "yaml"),
+    ("application/x-latex", "tex"),
+    ("application/x-tex", "tex"),
+    ("text/x-diff", "diff"),
+    ("text/x-patch", "diff"),
+];
+
+// How each lexer's language writes a comment on a line of its own, as an opening and a closing
+// part. Languages missing here, like JSON, have nothing that reliably works as one.
+const LINE_COMMENTS: &[(&str, &str, &str)] = &[
+    ("applescript", "-- ", ""),
+    ("arduino", "// ", ""),
+    ("bash", "# ", ""),
+    ("bat", "REM ", ""),
+    ("c", "// ", ""),
+    ("clojure", "; ", ""),
+    ("cmake", "# ", ""),
+    ("coffee-script", "# ", ""),
+    ("common-lisp", "; ", ""),
+    ("cpp", "// ", ""),
+    ("csharp", "// ", ""),
+    ("css", "/* ", " */"),
+    ("cuda", "// ", ""),
+    ("dart", "// ", ""),
+    ("delphi", "// ", ""),
+    ("django", "{# ", " #}"),
+    ("dker", "# ", ""),
+    ("elixir", "# ", ""),
+    ("erlang", "% ", ""),
+    ("go", "// ", ""),
+    ("handlebars", "{{! ", " }}"),
+    ("haskell", "-- ", ""),
+    ("html", "<!-- ", " -->"),
+    ("html+django", "{# ", " #}"),
+    ("ini", "; ", ""),
+    ("java", "// ", ""),
+    ("js", "// ", ""),
+    ("jsx", "// ", ""),
+    ("kotlin", "// ", ""),
+    ("less", "// ", ""),
+    ("lua", "-- ", ""),
+    ("make", "# ", ""),
+    ("matlab", "% ", ""),
+    ("nginx", "# ", ""),
+    ("numpy", "# ", ""),
+    ("objective-c", "// ", ""),
+    ("perl", "# ", ""),
+    ("postgresql", "-- ", ""),
+    ("python", "# ", ""),
+    ("rb", "# ", ""),
+    ("rust", "// ", ""),
+    ("sass", "// ", ""),
+    ("scss", "// ", ""),
+    ("sol", "// ", ""),
+    ("sql", "-- ", ""),
+    ("swift", "// ", ""),
+    ("tex", "% ", ""),
+    ("vim", "\" ", ""),
+    ("xml", "<!-- ", " -->"),
+    ("xslt", "<!-- ", " -->"),
+    ("yaml", "# ", ""),
+    ("_markdown", "<!-- ", " -->"),
+    ("_rst", ".. ", ""),
+    ("rst", ".. ", ""),
+];
+
+// Lookup tables built from the defaults above plus the user's langs.toml
+struct LangTable {
+    filenames: HashMap<String, String>,
+    extensions: HashMap<String, String>,
+}
+
+// Format of `<config dir>/zero-paste/langs.toml`:
+//
+//     [extensions]
+//     zig = "rust"
+//
+//     [filenames]
+//     justfile = "make"
+#[derive(Default, serde::Deserialize)]
+struct LangOverrides {
+    #[serde(default)]
+    filenames: HashMap<String, String>,
+    #[serde(default)]
+    extensions: HashMap<String, String>,
+}
+
+impl LangTable {
+    fn load() -> Self {
+        let mut table = LangTable {
+            filenames: SPECIAL_FILENAMES.iter().map(|(name, lang)| (name.to_string(), lang.to_string())).collect(),
+            extensions: EXTENSIONS.iter()
+                .flat_map(|(lang, exts)| exts.iter().map(|ext| (ext.to_string(), lang.to_string())))
+                .chain(COMPOUND_EXTENSIONS.iter().map(|(ext, lang)| (ext.to_string(), lang.to_string())))
+                .collect(),
+        };
+
+        let overrides = crate::config_dir().map(|dir| dir.join("langs.toml")).and_then(|path| {
+            let content = std::fs::read_to_string(&path).ok()?;
+            toml::from_str::<LangOverrides>(&content)
+                .map_err(|e| tracing::warn!("ignoring {}: {}", path.display(), e))
+                .ok()
+        }).unwrap_or_default();
+
+        let merge = |map: &mut HashMap<String, String>, entries: HashMap<String, String>| {
+            for (key, lang) in entries {
+                if !SUPPORTED_LANG.contains(&lang.as_str()) {
+                    tracing::warn!("ignoring unsupported language {} for {} in langs.toml", lang, key);
+                    continue;
+                }
+                map.insert(key.to_lowercase(), lang);
+            }
+        };
+        merge(&mut table.filenames, overrides.filenames);
+        merge(&mut table.extensions, overrides.extensions);
+        table
+    }
+
+    fn get() -> &'static LangTable {
+        static TABLE: OnceLock<LangTable> = OnceLock::new();
+        TABLE.get_or_init(LangTable::load)
+    }
+}
+
+pub fn map_filename_to_lang(file: &str) -> Option<String> {
+    let table = LangTable::get();
+
+    // Only the file name itself counts, and `C:\src\main.rs` is a path on any platform
+    let file = file.rsplit(['/', '\\']).next().unwrap_or(file);
+
+    // Convert the file name to lowercase for case-insensitive matching
+    let file_lower = file.to_lowercase();
+
+    // Handle special cases that don't follow the regular file extension pattern
+    if let Some(lang) = table.filenames.get(&file_lower) {
+        return Some(lang.clone());
+    }
+    if file_lower.contains("nginx") {
+        return Some("nginx".to_string());
+    }
+    // Variants like Dockerfile.dev, where the extension names the environment rather than the format
+    if file_lower.starts_with("dockerfile.") {
+        return Some("dker".to_string());
+    }
+
+    // Detect the wrapped file instead, falling back to the wrapper's own mapping below
+    if let Some((stem, ext)) = file_lower.rsplit_once('.') {
+        if TEMPLATE_WRAPPERS.contains(&ext) {
+            if let Some(lang) = map_filename_to_lang(stem) {
+                return Some(lang);
+            }
+        }
+    }
+
+    // Try the longest extension first so compound ones win over their last part
+    let mut rest = file_lower.as_str();
+    while let Some((_, ext)) = rest.split_once('.') {
+        if let Some(lang) = table.extensions.get(ext) {
+            return Some(lang.clone());
+        }
+        rest = ext;
+    }
+
+    mime_guess::from_path(&file_lower).iter().find_map(|mime| lang_from_mime_type(mime.essence_str()))
+}
+
+/// Formats `text` as a comment line in the language of `lang`, or `None` without a comment syntax.
+pub fn line_comment(lang: &str, text: &str) -> Option<String> {
+    let (_, open, close) = LINE_COMMENTS.iter().find(|(known, _, _)| *known == lang)?;
+    Some(format!("{}{}{}\n", open, text, close))
+}
+
+/// Maps a MIME type such as `application/json` to a lexer, including `+json` and `+xml` types.
+pub fn lang_from_mime_type(mime: &str) -> Option<String> {
+    if let Some((_, lang)) = MIME_TYPES.iter().find(|(known, _)| known.eq_ignore_ascii_case(mime)) {
+        return Some(lang.to_string());
+    }
+    let (_, suffix) = mime.rsplit_once('+')?;
+    match suffix.to_ascii_lowercase().as_str() {
+        "json" => Some("json".to_string()),
+        "xml" => Some("xml".to_string()),
+        _ => None,
+    }
+}
+
+/// Detects the language of an upload, from its file name first and then from its content.
+pub fn detect_lang(filename: Option<&str>, content: &[u8]) -> Option<String> {
+    filename.and_then(map_filename_to_lang).or_else(|| {
+        let first_line = content.split(|b| *b == b'\n').next()?;
+        lang_from_shebang(&String::from_utf8_lossy(first_line))
+    }).or_else(|| {
+        let content = String::from_utf8_lossy(content);
+        lang_from_modeline(&content)
+    }).or_else(|| {
+        magic_mime_type(content).and_then(lang_from_mime_type)
+    }).or_else(|| {
+        guess_lang_from_content(&String::from_utf8_lossy(content))
+    })
+}
+
+/// MIME type of content recognized by its magic bytes, like `image/png` or `text/html`. Always
+/// `None` without the `magic` feature.
+#[cfg(feature = "magic")]
+pub fn magic_mime_type(content: &[u8]) -> Option<&'static str> {
+    infer::get(content).map(|kind| kind.mime_type())
+}
+
+#[cfg(not(feature = "magic"))]
+pub fn magic_mime_type(_content: &[u8]) -> Option<&'static str> {
+    None
+}
+
+pub fn lang_from_shebang(first_line: &str) -> Option<String> {
+    let command = first_line.strip_prefix("#!")?;
+    let mut words = command.split_whitespace();
+
+    // `#!/usr/bin/env [-S] python3` names the interpreter after env and its flags
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        interpreter = words.find(|word| !word.starts_with('-'))?;
+    }
+
+    // Drop version suffixes such as python3.12 or php8
+    let interpreter = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    let lang = match interpreter {
+        "python" => "python",
+        "sh" | "bash" | "dash" | "ksh" | "zsh" => "bash",
+        "node" | "nodejs" => "js",
+        "ruby" => "rb",
+        "perl" => "perl",
+        "php" => "php",
+        _ => return None,
+    };
+    Some(lang.to_string())
+}
+
+/// Looks for a vim (`vim: set ft=python:`) or emacs (`-*- mode: ruby -*-`) modeline near the start or end of `content`.
+pub fn lang_from_modeline(content: &str) -> Option<String> {
+    // Editors only honor modelines in the first and last few lines
+    const MODELINE_LINES: usize = 5;
+
+    let vim = Regex::new(r"(?:^|\s)(?:vi|vim|ex):.*?\b(?:ft|filetype)=([\w+-]+)").unwrap();
+    let emacs = Regex::new(r"-\*-(.*?)-\*-").unwrap();
+    let emacs_mode = Regex::new(r"(?:^|;)\s*mode:\s*([\w+-]+)").unwrap();
+
+    let lines: Vec<&str> = content.lines().collect();
+    let tail = lines.len().saturating_sub(MODELINE_LINES).max(MODELINE_LINES.min(lines.len()));
+    let candidates = lines.iter().take(MODELINE_LINES).chain(&lines[tail..]);
+
+    for line in candidates {
+        if let Some(caps) = vim.captures(line) {
+            return lang_from_filetype(&caps[1]);
+        }
+        if let Some(caps) = emacs.captures(line) {
+            // Either `-*- mode: ruby; coding: utf-8 -*-` or the short `-*- ruby -*-` form
+            let vars = caps[1].trim();
+            let mode = match emacs_mode.captures(vars) {
+                Some(mode) => mode.get(1).map(|m| m.as_str()),
+                None if !vars.contains(':') => Some(vars),
+                None => None,
+            };
+            if let Some(lang) = mode.and_then(lang_from_filetype) {
+                return Some(lang);
+            }
+        }
+    }
+
+    None
+}
+
+// Maps vim filetypes and emacs major modes onto the lexer names in `SUPPORTED_LANG`
+fn lang_from_filetype(filetype: &str) -> Option<String> {
+    let filetype = filetype.to_lowercase();
+    let filetype = filetype.strip_suffix("-mode").unwrap_or(&filetype);
+
+    let lang = match filetype {
+        "text" | "txt" => "_text",
+        "markdown" | "gfm" => "_markdown",
+        "sh" | "bash" | "zsh" | "shell-script" => "bash",
+        "c++" => "cpp",
+        "cs" => "csharp",
+        "dockerfile" => "dker",
+        "dosini" | "conf" => "ini",
+        "javascript" | "typescript" => "js",
+        "javascriptreact" | "typescriptreact" => "jsx",
+        "lisp" => "common-lisp",
+        "objc" => "objective-c",
+        "cperl" => "perl",
+        "ruby" => "rb",
+        "plaintex" => "tex",
+        "yml" => "yaml",
+        other if SUPPORTED_LANG.contains(&other) && !other.starts_with('_') => other,
+        _ => return None,
+    };
+    Some(lang.to_string())
+}
+
+/// Last-resort guess from what the content looks like. Only answers when the signals are unambiguous.
+pub fn guess_lang_from_content(content: &str) -> Option<String> {
+    let trimmed = content.trim_start();
+
+    let lang = if trimmed.starts_with("<?php") {
+        "php"
+    } else if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde::de::IgnoredAny>(content).is_ok() {
+        "json"
+    } else if looks_like_rust(content) {
+        "rust"
+    } else if looks_like_python(content) {
+        "python"
+    } else {
+        return None;
+    };
+    Some(lang.to_string())
+}
+
+fn looks_like_rust(content: &str) -> bool {
+    let opens = content.matches('{').count();
+    let balanced = opens > 0 && opens == content.matches('}').count();
+    let function = Regex::new(r"\bfn\s+\w+\s*[<(]").unwrap();
+    let binding = Regex::new(r"\blet\s+(mut\s+)?\w+|\buse\s+\w+::").unwrap();
+
+    balanced && function.is_match(content) && binding.is_match(content)
+}
+
+fn looks_like_python(content: &str) -> bool {
+    let definition = Regex::new(r"(?m)^\s*(def|class)\s+\w+.*:\s*$").unwrap();
+    let import = Regex::new(r"(?m)^(import\s+\w+|from\s+[\w.]+\s+import\s+)").unwrap();
+    let block = Regex::new(r"(?m)^\s*(if|for|while|with|try)\b.*:\s*$").unwrap();
+
+    definition.is_match(content) || (import.is_match(content) && block.is_match(content))
+}
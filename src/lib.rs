@@ -0,0 +1,695 @@
+mod archive;
+pub mod backend;
+mod command;
+mod comments;
+mod config;
+pub mod crypto;
+mod error;
+mod expire;
+mod history;
+mod lang;
+mod minify;
+mod remote;
+mod syntax;
+mod text;
+
+pub use archive::{archive_dir, DEFAULT_ARCHIVE_DEPTH};
+pub use backend::{DpasteBackend, Fallback, GistBackend, HasteBackend, IxIoBackend, MozillaPaste, NullPointerBackend, PasteBackend, PastebinComBackend, PrivateBinBackend, Retry, SprungeBackend, TermbinBackend, UploadOptions, Uploaded, Visibility, WebhookBackend, BASE_URL};
+pub use command::{run_command, run_detector, DETECTOR_HEAD, DETECTOR_TIMEOUT};
+pub use comments::strip_comments;
+pub use config::{Config, PastebinConfig, WebhookConfig};
+pub use error::PasteError;
+pub use expire::{format_expiry, parse_expire, parse_expiry, Expire, ExpireSpec, ParseError, SUPPORTED_EXPIRE};
+pub use history::HistoryEntry;
+pub use lang::{detect_lang, guess_lang_from_content, lang_from_mime_type, lang_from_modeline, lang_from_shebang, line_comment, magic_mime_type, map_filename_to_lang, SUPPORTED_LANG};
+pub use minify::minify;
+pub use remote::{fetch_url, is_remote};
+pub use syntax::check_syntax;
+pub use text::{add_lines, decode_base64, encode_base64, expand_tabs, first_line_title, normalize_eol, slice_lines, trim_trailing, wrap_lines, LineRange};
+
+/// How `upload_file` reads and labels its input.
+#[derive(Debug, Clone, Default)]
+pub struct FileOptions {
+    pub expire: ExpireSpec,
+    /// `expire` was asked for, rather than left at the one-time default
+    pub explicit_expire: bool,
+    /// Explicit lexer, detected from the file when `None`
+    pub lang: Option<String>,
+    /// Lexer for content nothing could be detected from, `_code` when `None`
+    pub default_lang: Option<String>,
+    pub title: Option<String>,
+    /// Without a `title`, take it from the first line of text, see `first_line_title`
+    pub title_from_first_line: bool,
+    /// Lossily decode content that isn't valid UTF-8 instead of refusing it
+    pub force: bool,
+    /// Convert CRLF and CR line endings to LF
+    pub normalize_eol: bool,
+    /// Strip trailing spaces and tabs from every line
+    pub trim_trailing: bool,
+    /// Replace tabs with spaces, with tab stops this many columns apart
+    pub expand_tabs: Option<usize>,
+    /// Break lines longer than this many characters
+    pub wrap: Option<usize>,
+    /// Minify JSON, CSS and JavaScript, see `minify`
+    pub minify: bool,
+    /// Remove comments, in the languages `strip_comments` knows, before any lines are added
+    pub strip_comments: bool,
+    /// Refuse JSON, YAML and TOML that doesn't parse, unless `force` is set
+    pub syntax_check: bool,
+    /// Lines added above the content, once it's been checked, rewritten and minified
+    pub prepend: Vec<String>,
+    /// Lines added below the content, once it's been checked, rewritten and minified
+    pub append_text: Vec<String>,
+    /// Only upload these lines of the file
+    pub lines: Option<LineRange>,
+    pub encryption: Option<crypto::Encryption>,
+    /// Start the paste with a comment naming the file, in the syntax of its language
+    pub with_filename: bool,
+    /// How deep directories are archived, `DEFAULT_ARCHIVE_DEPTH` when `None`
+    pub max_depth: Option<usize>,
+    /// Archive files that `.gitignore` and the like exclude as well
+    pub no_ignore: bool,
+    /// File name to detect the language of stdin from, as if that was read from a file
+    pub stdin_name: Option<String>,
+    /// Command asked for the language before the built-in detection, see `run_detector`
+    pub detector_command: Option<String>,
+    /// Upload the content base64-encoded as plain text, which lets binary files onto text backends
+    pub base64: bool,
+}
+
+impl FileOptions {
+    // Streaming skips everything that needs the whole content, checking it included
+    fn rewrites_content(&self) -> bool {
+        self.text_options_set() || self.encryption.is_some() || self.base64
+    }
+
+    // Options that only mean something for text, which an archive isn't
+    fn text_options_set(&self) -> bool {
+        self.normalize_eol || self.trim_trailing || self.expand_tabs.is_some() || self.wrap.is_some() || self.minify || self.strip_comments || self.syntax_check
+            || self.lines.is_some() || !self.prepend.is_empty() || !self.append_text.is_empty() || self.with_filename || self.title_from_first_line
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PasteResult {
+    pub url: reqwest::Url,
+    pub lang: String,
+    /// The expire time the service applied, `None` if it keeps the paste for as long as it likes
+    pub expires: Option<ExpireSpec>,
+    /// Secret needed to delete the paste later, for services that hand one out
+    pub delete_token: Option<String>,
+    /// Length of the uploaded content in bytes
+    pub bytes: u64,
+    /// Hex SHA-256 of the uploaded content
+    pub sha256: String,
+    /// Name of the backend that created the paste, when one of several was picked
+    pub backend: Option<String>,
+}
+
+/// Settings for the HTTP client shared by all backends.
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    /// Limit for each request, `None` waits forever
+    pub timeout: Option<std::time::Duration>,
+    /// Proxy for all requests. Without one, `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` are honored.
+    pub proxy: Option<String>,
+    /// Extra `Name: Value` headers for every request. Headers a backend sets itself, like the
+    /// `Content-Type` of a form, take precedence.
+    pub headers: Vec<String>,
+    /// `User-Agent` for every request, `browser` for one that looks like Chrome. Defaults to
+    /// `zero-paste/<version>`.
+    pub user_agent: Option<String>,
+    /// How many redirects a request may follow before it fails
+    pub max_redirects: usize,
+}
+
+/// Redirects a request follows unless `ClientOptions` says otherwise. Uploads take one at most.
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+// For services that turn away anything that doesn't look like a browser
+const BROWSER_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/129.0.0.0 Safari/537.36";
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        ClientOptions {
+            timeout: Some(std::time::Duration::from_secs(30)),
+            proxy: None,
+            headers: Vec::new(),
+            user_agent: None,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+        }
+    }
+}
+
+// Where zero-paste looks for its configuration files, e.g. ~/.config/zero-paste on Linux
+pub(crate) fn config_dir() -> Option<std::path::PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("zero-paste"))
+}
+
+/// Builds an HTTP client suitable for uploads. Cloning it is cheap and shares the connection pool.
+pub fn client(opts: &ClientOptions) -> Result<reqwest::Client, PasteError> {
+    let mut builder = reqwest::ClientBuilder::new()
+        .cookie_store(true)
+        .redirect(redirect_policy(opts.max_redirects))
+        .user_agent(match opts.user_agent.as_deref() {
+            Some("browser") => BROWSER_USER_AGENT.to_string(),
+            Some(user_agent) => user_agent.to_string(),
+            None => concat!("zero-paste/", env!("CARGO_PKG_VERSION")).to_string(),
+        });
+    if let Some(timeout) = opts.timeout {
+        builder = builder.timeout(timeout);
+    }
+    if let Some(proxy) = &opts.proxy {
+        builder = builder.proxy(parse_proxy(proxy)?);
+    }
+    if !opts.headers.is_empty() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        for header in &opts.headers {
+            let (name, value) = parse_header(header)?;
+            headers.append(name, value);
+        }
+        builder = builder.default_headers(headers);
+    }
+    Ok(builder.build()?)
+}
+
+// Like `Policy::limited`, but every hop is logged so it's clear how the final URL was reached
+fn redirect_policy(max_redirects: usize) -> reqwest::redirect::Policy {
+    reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() > max_redirects {
+            return attempt.error(format!("too many redirects, the limit is {}", max_redirects));
+        }
+        if let Some(from) = attempt.previous().last() {
+            tracing::debug!("{} redirected to {}", from, attempt.url());
+        }
+        attempt.follow()
+    })
+}
+
+fn parse_header(header: &str) -> Result<(reqwest::header::HeaderName, reqwest::header::HeaderValue), PasteError> {
+    let invalid = |reason: String| PasteError::InvalidHeader { header: header.to_string(), reason };
+
+    let (name, value) = header.split_once(':').ok_or_else(|| invalid("expected `Name: Value`".to_string()))?;
+    let name = reqwest::header::HeaderName::from_bytes(name.trim().as_bytes()).map_err(|e| invalid(e.to_string()))?;
+    let value = reqwest::header::HeaderValue::from_str(value.trim()).map_err(|e| invalid(e.to_string()))?;
+    Ok((name, value))
+}
+
+// An explicit proxy replaces the ones from the environment, but hosts in NO_PROXY still bypass it
+fn parse_proxy(proxy: &str) -> Result<reqwest::Proxy, PasteError> {
+    let invalid = |reason: String| PasteError::InvalidProxy { proxy: proxy.to_string(), reason };
+
+    let url = reqwest::Url::parse(proxy).map_err(|e| invalid(e.to_string()))?;
+    if !matches!(url.scheme(), "http" | "https" | "socks5" | "socks5h") {
+        return Err(invalid(format!("unsupported scheme `{}`", url.scheme())));
+    }
+    let proxy = reqwest::Proxy::all(url).map_err(|e| invalid(e.to_string()))?;
+    Ok(proxy.no_proxy(reqwest::NoProxy::from_env()))
+}
+
+/// Uploads `content` to paste.mozilla.org. Without an explicit `lang` the paste is highlighted as `_code`.
+pub async fn upload(content: &str, expire: ExpireSpec, lang: Option<&str>, title: Option<&str>) -> Result<PasteResult, PasteError> {
+    let lang = check_lang(lang)?.unwrap_or("_code");
+    let backend = MozillaPaste::new(client(&ClientOptions::default())?);
+    let paste = PreparedPaste {
+        content: PasteContent::Bytes(content.as_bytes().to_vec()),
+        opts: UploadOptions {
+            expire,
+            explicit_expire: true,
+            lang: lang.to_string(),
+            title: title.unwrap_or_default().to_string(),
+            filename: None,
+        },
+    };
+    upload_prepared(&backend, &paste).await
+}
+
+/// Uploads the file at `file`, detecting the language from its name or content unless `lang` is given.
+/// A `file` of `-` reads the content from stdin, which must not be a terminal.
+/// The title defaults to the file's base name, or stays empty for stdin.
+pub async fn upload_file(backend: &dyn PasteBackend, file: &str, opts: &FileOptions) -> Result<PasteResult, PasteError> {
+    upload_prepared(backend, &prepare_file(backend, file, opts)?).await
+}
+
+// Files above this size are streamed by backends that can, instead of being read into memory
+const STREAM_THRESHOLD: u64 = 8 * 1024 * 1024;
+
+// How much of a streamed file language detection gets to look at
+const STREAM_HEAD: u64 = 64 * 1024;
+
+/// Content and options resolved from a file, exactly as `upload_prepared` will send them.
+#[derive(Debug, Clone)]
+pub struct PreparedPaste {
+    pub content: PasteContent,
+    pub opts: UploadOptions,
+}
+
+#[derive(Debug, Clone)]
+pub enum PasteContent {
+    Bytes(Vec<u8>),
+    /// A large file that is streamed from disk when it's uploaded
+    File { path: std::path::PathBuf, len: u64 },
+    /// Piped input too large to hold in memory: the part read to resolve its options, with the
+    /// rest still waiting on stdin until it's streamed with the upload
+    Stdin { head: Vec<u8> },
+}
+
+impl PasteContent {
+    /// Length in bytes, or for piped input the part read so far.
+    pub fn len(&self) -> u64 {
+        match self {
+            PasteContent::Bytes(content) => content.len() as u64,
+            PasteContent::File { len, .. } => *len,
+            PasteContent::Stdin { head } => head.len() as u64,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl PreparedPaste {
+    /// Hex SHA-256 of the content as it will be uploaded. Streamed files are hashed from disk, but
+    /// piped input can only be read once, by the upload, so it has none beforehand.
+    pub fn sha256(&self) -> Result<Option<String>, PasteError> {
+        use sha2::Digest;
+
+        let digest = match &self.content {
+            PasteContent::Bytes(content) => sha2::Sha256::digest(content),
+            PasteContent::File { path, .. } => {
+                use std::io::Read;
+
+                let mut file = std::fs::File::open(path)?;
+                let mut hasher = sha2::Sha256::new();
+                let mut buf = vec![0; 64 * 1024];
+                loop {
+                    match file.read(&mut buf)? {
+                        0 => break hasher.finalize(),
+                        n => hasher.update(&buf[..n]),
+                    }
+                }
+            }
+            PasteContent::Stdin { .. } => return Ok(None),
+        };
+        Ok(Some(hex(&digest)))
+    }
+}
+
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Reads `file` and resolves everything `upload_file` would send to `backend`, without uploading it.
+pub fn prepare_file(backend: &dyn PasteBackend, file: &str, opts: &FileOptions) -> Result<PreparedPaste, PasteError> {
+    let lang = check_lang(opts.lang.as_deref())?;
+
+    if file != "-" && std::fs::metadata(file).map_err(PasteError::reading(file))?.is_dir() {
+        return prepare_dir(backend, std::path::Path::new(file), opts);
+    }
+    if file != "-" && backend.supports_streaming() && !opts.rewrites_content() {
+        let len = std::fs::metadata(file).map_err(PasteError::reading(file))?.len();
+        if len > STREAM_THRESHOLD {
+            return prepare_stream(backend, file, len, lang, opts);
+        }
+    }
+
+    let (content, filename) = if file == "-" {
+        let (mut content, whole) = read_stdin_head(STREAM_THRESHOLD)?;
+        if !whole {
+            if backend.supports_streaming() && !opts.rewrites_content() {
+                return prepare_piped(backend, content, lang, opts);
+            }
+            let why = match backend.supports_streaming() {
+                true => "the options given need all of it to rewrite or check it",
+                false => "this backend can't stream; one that can, such as 0x0 or haste, would send it as it's read",
+            };
+            tracing::warn!("stdin is over {} MiB and is read into memory whole, since {}", STREAM_THRESHOLD >> 20, why);
+            std::io::Read::read_to_end(&mut std::io::stdin(), &mut content)?;
+        }
+        (content, opts.stdin_name.as_deref())
+    } else {
+        let path = std::path::Path::new(file);
+        (std::fs::read(file).map_err(PasteError::reading(file))?, path.file_name().and_then(|file| file.to_str()))
+    };
+    prepare_content(backend, content, filename, opts)
+}
+
+/// Resolves content that was already read, like `prepare_file` does with a file called `filename`.
+pub fn prepare_content(backend: &dyn PasteBackend, mut content: Vec<u8>, filename: Option<&str>, opts: &FileOptions) -> Result<PreparedPaste, PasteError> {
+    let lang = check_lang(opts.lang.as_deref())?;
+
+    // Detect from the whole file, since the snippet may lack the shebang or modeline
+    let detected = lang.is_none().then(|| detect(filename, &content, opts)).flatten();
+    if let Some(range) = opts.lines {
+        content = slice_lines(&content, range)?.to_vec();
+    }
+
+    // Text backends would reject it anyway, but this way the user learns about --force
+    if !backend.supports_binary() && !opts.base64 {
+        if let Err(e) = std::str::from_utf8(&content) {
+            if !opts.force {
+                return Err(magic_mime_type(&content).map_or(PasteError::NotUtf8, |mime| PasteError::BinaryContent(mime.to_string())));
+            }
+            tracing::warn!("invalid UTF-8 at byte {}, replacing undecodable bytes", e.valid_up_to());
+            content = String::from_utf8_lossy(&content).into_owned().into_bytes();
+        }
+    }
+
+    // Taken before any lines are added above it
+    let title = match (&opts.title, opts.title_from_first_line) {
+        (None, true) => std::str::from_utf8(&content).ok().and_then(first_line_title),
+        _ => None,
+    };
+
+    // The content is checked as it is in the file, before anything is added to it
+    if opts.syntax_check {
+        if let (Some(lang), Ok(text)) = (lang.or(detected.as_deref()), std::str::from_utf8(&content)) {
+            match check_syntax(lang, filename, text) {
+                Err(PasteError::InvalidSyntax { format, reason }) if opts.force => tracing::warn!("the content is not valid {}, uploading it anyway: {}", format, reason),
+                result => result?,
+            }
+        }
+    }
+    if opts.strip_comments {
+        if let Some(lang) = lang.or(detected.as_deref()) {
+            content = strip_content(content, lang);
+        }
+    }
+    if opts.expand_tabs.is_some() && lang.or(detected.as_deref()) == Some("make") {
+        tracing::warn!("makefiles need tabs to start recipes, expanding them will likely break this one");
+    }
+    if opts.normalize_eol || opts.trim_trailing || opts.expand_tabs.is_some() || opts.wrap.is_some() {
+        content = rewrite(content, opts);
+    }
+
+    let mut lang = lang.map(str::to_string).or(detected).or_else(|| opts.default_lang.clone()).unwrap_or("_code".to_string());
+    if opts.minify && !opts.base64 {
+        content = minify_content(content, &lang);
+    }
+    // After the transforms that need the content to stay valid in its language
+    if !opts.prepend.is_empty() || !opts.append_text.is_empty() {
+        content = add_lines(&content, &opts.prepend, &opts.append_text);
+    }
+    if opts.base64 {
+        content = encode_base64(&content).into_bytes();
+        lang = "_text".to_string();
+    } else if opts.with_filename {
+        match filename.and_then(|name| line_comment(&lang, name)) {
+            Some(header) => content = with_header(content, header.as_bytes()),
+            None => tracing::warn!("no file name to add, or no comment syntax for {}; leaving the content as it is", lang),
+        }
+    }
+
+    // Highlighting ciphertext as the language of its plaintext would only garble it
+    if let Some(encryption) = &opts.encryption {
+        content = encryption.encrypt(&content)?;
+        lang = "_text".to_string();
+        match encryption {
+            crypto::Encryption::Recipient(recipient) => tracing::info!("the paste is encrypted for {}; read it with `paste get --decrypt`", recipient),
+            crypto::Encryption::Password(_) => tracing::info!("the paste is encrypted with a password; read it with `paste get --password`"),
+        }
+    }
+
+    let mut opts = upload_options(opts, lang, filename);
+    if let Some(title) = title {
+        opts.title = title;
+    }
+    tracing::debug!("resolved {}: {} bytes, lexer {}, expire {}, title {:?}", filename.unwrap_or("input"), content.len(), opts.lang, opts.expire, opts.title);
+    Ok(PreparedPaste { content: PasteContent::Bytes(content), opts })
+}
+
+// The user's detector gets the first say, if there is one
+fn detect(filename: Option<&str>, content: &[u8], opts: &FileOptions) -> Option<String> {
+    opts.detector_command.as_deref()
+        .and_then(|command| run_detector(command, filename, content))
+        .or_else(|| detect_lang(filename, content))
+}
+
+// Minifying is only ever a nicety, so whatever goes wrong leaves the content as it was
+fn minify_content(content: Vec<u8>, lang: &str) -> Vec<u8> {
+    let Ok(text) = std::str::from_utf8(&content) else {
+        tracing::warn!("content isn't text, uploading it without minifying");
+        return content;
+    };
+    match minify(lang, text) {
+        Ok(Some(minified)) => {
+            tracing::debug!("minified {} bytes of {} to {}", content.len(), lang, minified.len());
+            minified.into_bytes()
+        }
+        Ok(None) => {
+            tracing::info!("{} can't be minified safely, uploading it as it is", lang);
+            content
+        }
+        Err(e) => {
+            tracing::warn!("could not minify the content as {}, uploading the original: {}", lang, e);
+            content
+        }
+    }
+}
+
+// Like minifying, a language without comment rules or content that isn't text is left alone
+fn strip_content(content: Vec<u8>, lang: &str) -> Vec<u8> {
+    let Ok(text) = std::str::from_utf8(&content) else {
+        tracing::warn!("content isn't text, uploading it with its comments");
+        return content;
+    };
+    match strip_comments(lang, text) {
+        Some(stripped) => {
+            tracing::debug!("stripped comments from {} bytes of {}, leaving {}", content.len(), lang, stripped.len());
+            stripped.into_bytes()
+        }
+        None => {
+            tracing::info!("no comment syntax is known for {}, uploading it with its comments", lang);
+            content
+        }
+    }
+}
+
+// The header goes below a shebang, which only works on the first line
+fn with_header(content: Vec<u8>, header: &[u8]) -> Vec<u8> {
+    let at = if content.starts_with(b"#!") {
+        content.iter().position(|b| *b == b'\n').map_or(content.len(), |i| i + 1)
+    } else {
+        0
+    };
+    let mut with_header = Vec::with_capacity(content.len() + header.len());
+    with_header.extend_from_slice(&content[..at]);
+    if at == content.len() && !content.is_empty() && !content.ends_with(b"\n") {
+        with_header.push(b'\n');
+    }
+    with_header.extend_from_slice(header);
+    with_header.extend_from_slice(&content[at..]);
+    with_header
+}
+
+// Directories go up as a .tar.gz, which is nothing to highlight or rewrite
+fn prepare_dir(backend: &dyn PasteBackend, dir: &std::path::Path, opts: &FileOptions) -> Result<PreparedPaste, PasteError> {
+    if !backend.supports_binary() && !opts.base64 {
+        return Err(PasteError::ArchiveNeedsBinary(dir.to_path_buf()));
+    }
+    if opts.text_options_set() {
+        tracing::warn!("{} is uploaded as an archive, ignoring the options for text", dir.display());
+    }
+
+    let mut content = archive_dir(dir, opts.max_depth.unwrap_or(DEFAULT_ARCHIVE_DEPTH), !opts.no_ignore)?;
+    if opts.base64 {
+        content = encode_base64(&content).into_bytes();
+    }
+    if let Some(encryption) = &opts.encryption {
+        content = encryption.encrypt(&content)?;
+    }
+
+    let opts = upload_options(opts, "_text".to_string(), Some(&format!("{}.tar.gz", archive::dir_name(dir))));
+    tracing::debug!("resolved {}: {} bytes of archive", dir.display(), content.len());
+    Ok(PreparedPaste { content: PasteContent::Bytes(content), opts })
+}
+
+fn rewrite(content: Vec<u8>, opts: &FileOptions) -> Vec<u8> {
+    let mut text = match String::from_utf8(content) {
+        Ok(text) => text,
+        Err(e) => {
+            tracing::warn!("content isn't text, leaving its line endings and whitespace alone");
+            return e.into_bytes();
+        }
+    };
+    if opts.normalize_eol {
+        text = normalize_eol(&text);
+    }
+    if opts.trim_trailing {
+        text = trim_trailing(&text);
+    }
+    if let Some(width) = opts.expand_tabs {
+        text = expand_tabs(&text, width);
+    }
+    // After the tabs are expanded, so they count as the columns they take up
+    if let Some(width) = opts.wrap {
+        text = wrap_lines(&text, width);
+    }
+    text.into_bytes()
+}
+
+// Like the rest of prepare_file, but only the head of the file is read to detect its language
+fn prepare_stream(backend: &dyn PasteBackend, file: &str, len: u64, lang: Option<&str>, opts: &FileOptions) -> Result<PreparedPaste, PasteError> {
+    use std::io::Read;
+
+    let mut head = Vec::new();
+    std::fs::File::open(file).map_err(PasteError::reading(file))?.take(STREAM_HEAD).read_to_end(&mut head)?;
+
+    let path = std::path::Path::new(file);
+    let filename = path.file_name().and_then(|file| file.to_str());
+    let opts = stream_options(backend, &head, filename, lang, opts)?;
+    tracing::debug!("resolved {}: streaming {} bytes, lexer {}, expire {}, title {:?}", file, len, opts.lang, opts.expire, opts.title);
+    Ok(PreparedPaste { content: PasteContent::File { path: path.to_path_buf(), len }, opts })
+}
+
+// Like prepare_stream, for stdin that turned out to be larger than STREAM_THRESHOLD
+fn prepare_piped(backend: &dyn PasteBackend, head: Vec<u8>, lang: Option<&str>, opts: &FileOptions) -> Result<PreparedPaste, PasteError> {
+    let start = &head[..head.len().min(STREAM_HEAD as usize)];
+    let opts = stream_options(backend, start, opts.stdin_name.as_deref(), lang, opts)?;
+    tracing::debug!("resolved stdin: streaming over {} bytes, lexer {}, expire {}, title {:?}", head.len(), opts.lang, opts.expire, opts.title);
+    Ok(PreparedPaste { content: PasteContent::Stdin { head }, opts })
+}
+
+// Options for content that's never in memory as a whole, from its first STREAM_HEAD bytes
+fn stream_options(backend: &dyn PasteBackend, head: &[u8], filename: Option<&str>, lang: Option<&str>, opts: &FileOptions) -> Result<UploadOptions, PasteError> {
+    // The head may end in the middle of a character, which is fine. --force can't
+    // rewrite content that's never in memory, so invalid text is refused either way.
+    if !backend.supports_binary() {
+        if let Err(e) = std::str::from_utf8(head) {
+            if e.error_len().is_some() {
+                return Err(PasteError::NotUtf8);
+            }
+        }
+    }
+
+    let lang = lang.map(str::to_string).or_else(||
+        detect(filename, head, opts)
+    ).or_else(|| opts.default_lang.clone()).unwrap_or("_code".to_string());
+    Ok(upload_options(opts, lang, filename))
+}
+
+fn upload_options(opts: &FileOptions, lang: String, filename: Option<&str>) -> UploadOptions {
+    UploadOptions {
+        expire: opts.expire,
+        explicit_expire: opts.explicit_expire,
+        lang,
+        title: opts.title.as_deref().or(filename).unwrap_or_default().to_string(),
+        filename: filename.map(str::to_string),
+    }
+}
+
+// Hex digits of a SHA-256 shown where the whole hash would be clutter
+const SHORT_SHA256: usize = 8;
+
+/// Uploads content returned by `prepare_file`.
+pub async fn upload_prepared(backend: &dyn PasteBackend, paste: &PreparedPaste) -> Result<PasteResult, PasteError> {
+    // What exactly goes out, after every rewrite and encryption. Piped input is hashed as it's sent.
+    let sha256 = paste.sha256()?;
+    if let Some(sha256) = &sha256 {
+        tracing::debug!("uploading {} bytes with SHA-256 {}", paste.content.len(), &sha256[..SHORT_SHA256]);
+    }
+    let (uploaded, bytes, sha256) = match &paste.content {
+        PasteContent::Bytes(content) => (backend.upload_bytes(content, &paste.opts).await?, content.len() as u64, sha256.unwrap_or_default()),
+        PasteContent::File { path, len } => {
+            let file = tokio::fs::File::open(path).await?;
+            let body = reqwest::Body::wrap_stream(tokio_util::io::ReaderStream::new(file));
+            (backend.upload_stream(body, Some(*len), &paste.opts).await?, *len, sha256.unwrap_or_default())
+        }
+        PasteContent::Stdin { head } => upload_piped(backend, head, &paste.opts).await?,
+    };
+    Ok(PasteResult {
+        url: uploaded.url,
+        lang: paste.opts.lang.clone(),
+        expires: uploaded.expires,
+        delete_token: uploaded.delete_token,
+        bytes,
+        sha256,
+        backend: uploaded.backend,
+    })
+}
+
+// Sends `head` and then the rest of stdin as it's read, counting and hashing it on the way since
+// it can't be read again
+async fn upload_piped(backend: &dyn PasteBackend, head: &[u8], opts: &UploadOptions) -> Result<(Uploaded, u64, String), PasteError> {
+    use futures::StreamExt;
+    use sha2::Digest;
+
+    let sent = std::sync::Arc::new(std::sync::Mutex::new((0u64, sha2::Sha256::new())));
+    let counter = sent.clone();
+    let head = futures::stream::once(std::future::ready(Ok(tokio_util::bytes::Bytes::copy_from_slice(head))));
+    let stream = head.chain(tokio_util::io::ReaderStream::new(tokio::io::stdin())).inspect(move |chunk| {
+        if let Ok(chunk) = chunk {
+            let mut sent = counter.lock().unwrap();
+            sent.0 += chunk.len() as u64;
+            sent.1.update(chunk);
+        }
+    });
+    let uploaded = backend.upload_stream(reqwest::Body::wrap_stream(stream), None, opts).await?;
+
+    let (bytes, hasher) = std::mem::take(&mut *sent.lock().unwrap());
+    let sha256 = hex(&hasher.finalize());
+    tracing::debug!("uploaded {} bytes of piped input with SHA-256 {}", bytes, &sha256[..SHORT_SHA256]);
+    Ok((uploaded, bytes, sha256))
+}
+
+// Reads stdin up to `limit` bytes, and whether that was all of it. When it wasn't, a byte more
+// than `limit` has been read and the rest is left for later.
+fn read_stdin_head(limit: u64) -> Result<(Vec<u8>, bool), PasteError> {
+    use std::io::{IsTerminal, Read};
+
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Err(PasteError::StdinIsTerminal);
+    }
+
+    let mut head = Vec::new();
+    stdin.lock().take(limit + 1).read_to_end(&mut head)?;
+    let whole = head.len() as u64 <= limit;
+    Ok((head, whole))
+}
+
+/// Reads all of stdin, refusing to wait on a terminal.
+pub fn read_stdin() -> Result<Vec<u8>, PasteError> {
+    use std::io::{IsTerminal, Read};
+
+    // Nothing is being piped in, so reading would just block waiting for the user
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Err(PasteError::StdinIsTerminal);
+    }
+
+    let mut content = Vec::new();
+    stdin.read_to_end(&mut content)?;
+    Ok(content)
+}
+
+/// Appends `content` to the buffer file at `path`, creating it if needed, on a line of its own.
+/// The file is locked meanwhile, so runs appending at the same time don't interleave.
+pub fn append_to_buffer(path: &std::path::Path, content: &[u8]) -> Result<(), PasteError> {
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    let mut file = std::fs::OpenOptions::new().read(true).append(true).create(true).open(path)?;
+    file.lock()?;
+    let mut last = [0];
+    let ends_mid_line = match file.seek(SeekFrom::End(-1)) {
+        Ok(_) => file.read_exact(&mut last).is_ok() && last[0] != b'\n',
+        // Seeking before the start means the buffer is empty
+        Err(_) => false,
+    };
+    let mut chunk = Vec::with_capacity(content.len() + 1);
+    if ends_mid_line && !content.is_empty() {
+        chunk.push(b'\n');
+    }
+    chunk.extend_from_slice(content);
+    file.write_all(&chunk)?;
+    file.unlock()?;
+    Ok(())
+}
+
+fn check_lang(lang: Option<&str>) -> Result<Option<&str>, PasteError> {
+    match lang {
+        Some(lang) if !SUPPORTED_LANG.contains(&lang) => Err(PasteError::UnsupportedLang(lang.to_string())),
+        lang => Ok(lang),
+    }
+}
@@ -1,169 +1,1841 @@
-use regex::Regex;
+use clap::builder::{PossibleValue, PossibleValuesParser, StringValueParser, TypedValueParser};
+use clap::error::ErrorKind;
+use clap::{ArgAction, CommandFactory, Parser, Subcommand, ValueEnum, ValueHint};
+use zero_paste::crypto::Encryption;
+use zero_paste::{ClientOptions, Config, DpasteBackend, Expire, ExpireSpec, Fallback, FileOptions, GistBackend, HasteBackend, HistoryEntry, IxIoBackend, LineRange, MozillaPaste, NullPointerBackend, PasteBackend, PastebinComBackend, PastebinConfig, PasteResult, PrivateBinBackend, PreparedPaste, Retry, SprungeBackend, TermbinBackend, Visibility, WebhookBackend, WebhookConfig, SUPPORTED_EXPIRE, SUPPORTED_LANG};
 
-const BASE_URL: &str = "https://paste.mozilla.org/";
-const SUPPORTED_LANG: [&str; 63] = ["_text", "_markdown", "_rst", "_code", "applescript", "arduino", "bash", "bat", "c", "clojure", "cmake", "coffee-script", "common-lisp", "console", "cpp", "csharp", "css", "cuda", "dart", "delphi", "diff", "django", "dker", "elixir", "erlang", "go", "handlebars", "haskell", "html", "html+django", "ini", "ipythonconsole", "irc", "java", "js", "json", "jsx", "kotlin", "less", "lua", "make", "matlab", "nginx", "numpy", "objective-c", "perl", "php", "postgresql", "python", "rb", "rst", "rust", "sass", "scss", "sol", "sql", "swift", "tex", "typoscript", "vim", "xml", "xslt", "yaml"];
-const SUPPORTED_EXPIRE: [&str; 5] = ["once", "1h", "1d", "1w", "21d"];
+#[derive(Parser)]
+#[command(name = "paste", version, about = "Upload files to paste.mozilla.org")]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+#[command(after_help = "Exit status:\n  0  success\n  1  any other failure\n  2  invalid usage or input\n  3  network error\n  4  the paste service rejected the request")]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Files to upload, `-` reads from stdin, directories are uploaded as a .tar.gz and http(s)
+    /// URLs are downloaded first
+    #[arg(required_unless_present_any = ["cmd", "files_from", "from_clipboard", "append"], value_name = "FILE")]
+    files: Vec<String>,
+
+    /// Also upload the files listed in MANIFEST, one path per line. Blank lines and lines
+    /// starting with `#` are skipped
+    #[arg(long, value_name = "MANIFEST", value_hint = ValueHint::FilePath, conflicts_with = "cmd")]
+    files_from: Option<std::path::PathBuf>,
+
+    /// Run a shell command and upload its combined output instead of files
+    #[arg(long, value_name = "COMMAND", conflicts_with = "files")]
+    cmd: Option<String>,
+
+    /// Append stdin to BUFFER, then upload all of BUFFER. The lexer is detected from its name
+    #[arg(long, value_name = "BUFFER", value_hint = ValueHint::FilePath, conflicts_with_all = ["files", "files_from", "cmd", "watch", "dry_run"])]
+    append: Option<std::path::PathBuf>,
+
+    /// Upload the text on the clipboard instead of files. --lang or --stdin-name pick the lexer
+    #[arg(long, conflicts_with_all = ["files", "files_from", "cmd", "watch"])]
+    from_clipboard: bool,
+
+    /// How long the paste is kept: once, or a duration such as 30m, 12h, 3d or 2w. Defaults to
+    /// $ZERO_PASTE_EXPIRE, then default_expire in the config file, then once
+    #[arg(short, long, value_parser = ExpireParser, hide_possible_values = true)]
+    expire: Option<ExpireSpec>,
+
+    /// Lexer used for highlighting, detected from the file name by default
+    #[arg(short, long, value_parser = PossibleValuesParser::new(SUPPORTED_LANG), hide_possible_values = true)]
+    lang: Option<String>,
+
+    /// Pick the lexer of stdin from the name of this file without reading it, e.g. for `jq .
+    /// data.json | paste --lang-from data.json -`. --lang takes precedence
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    lang_from: Option<std::path::PathBuf>,
+
+    /// Upload as plain text without highlighting, even over --lang
+    #[arg(long)]
+    no_highlight: bool,
+
+    /// Detect the language of stdin as if it was read from a file with this name, e.g. foo.py
+    #[arg(long, value_name = "NAME")]
+    stdin_name: Option<String>,
+
+    /// Title of the paste, defaults to the file name
+    #[arg(short, long)]
+    title: Option<String>,
+
+    /// Take the title from the first line of text, such as a Markdown heading, unless --title is given
+    #[arg(long)]
+    title_from_first_line: bool,
+
+    /// Paste service to upload to [default: mozilla]. Given a list like `mozilla,0x0`, each is
+    /// tried in turn until one succeeds
+    #[arg(short, long, value_enum, value_delimiter = ',')]
+    backend: Vec<Backend>,
+
+    /// Make the paste publicly listed, for backends that support it. Short for --visibility public
+    #[arg(long)]
+    public: bool,
+
+    /// Who can find the paste: public, unlisted or private. Gists are only ever public or
+    /// unlisted, and private pastebin.com pastes need a user key [default: unlisted]
+    #[arg(long, value_name = "LEVEL", conflicts_with = "public")]
+    visibility: Option<Visibility>,
+
+    /// Print the result as JSON
+    #[arg(long)]
+    json: bool,
+
+    /// Upload files that aren't valid UTF-8 anyway, replacing undecodable bytes. With --open, open
+    /// one-time pastes too
+    #[arg(long)]
+    force: bool,
+
+    /// Only upload lines START to END of the file, counting from 1
+    #[arg(long, value_name = "START:END")]
+    lines: Option<LineRange>,
+
+    /// Convert CRLF and CR line endings to LF
+    #[arg(long)]
+    normalize_eol: bool,
+
+    /// Strip trailing whitespace from every line
+    #[arg(long)]
+    trim_trailing: bool,
+
+    /// Replace tabs with spaces, with tab stops WIDTH columns apart, 4 unless given as --expand-tabs=8
+    #[arg(long, value_name = "WIDTH", num_args = 0..=1, require_equals = true, default_missing_value = "4", value_parser = clap::value_parser!(u16).range(1..))]
+    expand_tabs: Option<u16>,
+
+    /// Break lines longer than COLS characters, at a space where possible
+    #[arg(long, value_name = "COLS", value_parser = clap::value_parser!(u16).range(1..))]
+    wrap: Option<u16>,
+
+    /// Minify JSON, CSS and JavaScript before uploading; other languages are left alone
+    #[arg(long)]
+    minify: bool,
+
+    /// Remove comments from C-family, Rust, Python and shell code, keeping strings that contain
+    /// comment markers; other languages are left alone
+    #[arg(long)]
+    strip_comments: bool,
+
+    /// Refuse to upload JSON, YAML or TOML that doesn't parse, unless --force is given
+    #[arg(long)]
+    syntax_check: bool,
+
+    /// Add TEXT as a line above the content, e.g. "Repro for bug #123". Can be repeated
+    #[arg(long, value_name = "TEXT")]
+    prepend: Vec<String>,
+
+    /// Add TEXT as a line below the content. Can be repeated
+    #[arg(long, value_name = "TEXT")]
+    append_text: Vec<String>,
+
+    /// Upload the content base64-encoded as plain text, for binary files on text-only backends.
+    /// `paste get --base64` decodes it again
+    #[arg(long)]
+    base64: bool,
+
+    /// Start the paste with a comment naming the file, like `// main.rs`
+    #[arg(long)]
+    with_filename: bool,
+
+    /// How many levels deep directories are archived [default: 16]
+    #[arg(long, value_name = "N")]
+    max_depth: Option<usize>,
+
+    /// Also archive files excluded by .gitignore and similar files
+    #[arg(long)]
+    no_ignore: bool,
+
+    /// Encrypt the content to an age or SSH public key before uploading it
+    #[arg(long, value_name = "RECIPIENT")]
+    encrypt: Option<String>,
+
+    /// Encrypt the content with a password. Without one, a random key is generated and added to
+    /// the URL's fragment, which the server never sees
+    #[arg(long, value_name = "PASSWORD", num_args = 0..=1, require_equals = true, conflicts_with = "encrypt")]
+    password: Option<Option<String>>,
+
+    /// Print the URL of an earlier paste with the same content instead of uploading it again
+    #[arg(long)]
+    dedupe: bool,
+
+    /// Ask before uploading anything larger, e.g. 512K or 4M
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, default_value = "1M")]
+    max_size: u64,
+
+    /// Upload files larger than --max-size without asking
+    #[arg(short, long)]
+    yes: bool,
+
+    /// Ask for the expire time, language and title when they weren't given, suggesting the
+    /// defaults. Without a terminal to ask on, the defaults are used
+    #[arg(short, long, conflicts_with = "watch")]
+    interactive: bool,
+
+    /// Also copy the content to stdout, like tee, and print the results to stderr
+    #[arg(long, conflicts_with_all = ["dry_run", "watch"])]
+    tee: bool,
+
+    /// Print what would be uploaded without creating a paste
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Keep running and upload the files again as a new paste each time they're saved
+    #[arg(long, conflicts_with_all = ["cmd", "dry_run"])]
+    watch: bool,
+
+    /// Copy the paste URL to the clipboard
+    #[arg(long)]
+    copy: bool,
+
+    /// Print the paste URL as a QR code
+    #[arg(long)]
+    qr: bool,
+
+    /// Open the paste in the default browser. One-time pastes only with --force, as that view
+    /// would be their last
+    #[arg(long)]
+    open: bool,
+
+    /// Log HTTP details, twice for even more
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Only print the paste URLs and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Print nothing but the paste URLs, one per line, leaving out the details that otherwise go to
+    /// stderr
+    #[arg(long, conflicts_with_all = ["json", "dry_run", "watch"])]
+    raw: bool,
+
+    /// Print each paste as TEMPLATE instead of its URL, e.g. "[{file}]({url})" for a markdown link.
+    /// Placeholders are {url}, {file}, {lang}, {expires}, {expires_at}, {bytes} and {sha256};
+    /// {{ and }} stand for braces. Details still go to stderr unless --raw is given
+    #[arg(long, value_name = "TEMPLATE", value_parser = parse_template, conflicts_with = "json")]
+    format: Option<Template>,
+
+    /// When to style the output; auto styles it on terminals unless NO_COLOR is set
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t, global = true)]
+    color: ColorChoice,
+
+    /// Seconds to wait for each request, 0 waits forever
+    #[arg(long, value_name = "SECS", default_value_t = 30)]
+    timeout: u64,
+
+    /// How many redirects a request may follow
+    #[arg(long, value_name = "N", default_value_t = zero_paste::DEFAULT_MAX_REDIRECTS)]
+    max_redirects: usize,
+
+    /// How often to retry uploads that fail on connection errors or timeouts, or that the service
+    /// turns away with 429 Too Many Requests; those wait as long as its Retry-After header asks
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    retries: u32,
+
+    /// Upload at most N files at once. The default of 3 keeps most services from rate limiting a
+    /// batch; raise it for your own instance
+    #[arg(long, value_name = "N", default_value_t = 3, value_parser = clap::value_parser!(u16).range(1..))]
+    concurrency: u16,
+
+    /// Wait MS milliseconds between starting uploads of several files, for services that rate
+    /// limit even a few at once; 1000 with --concurrency 1 is as gentle as it gets
+    #[arg(long, value_name = "MS", default_value_t = 0)]
+    delay: u64,
+
+    /// Extra header for every request, e.g. "Authorization: Bearer ...". Can be repeated
+    #[arg(long, value_name = "NAME: VALUE")]
+    header: Vec<String>,
+
+    /// User-Agent for every request, `browser` to pose as Chrome [default: zero-paste/<version>]
+    #[arg(long, value_name = "UA")]
+    user_agent: Option<String>,
+
+    /// Fetch a fresh CSRF session instead of reusing the one cached by an earlier run
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Proxy for all requests, overrides HTTP_PROXY and HTTPS_PROXY
+    #[arg(long, value_name = "URL")]
+    proxy: Option<String>,
+
+    /// Base URL of a self-hosted instance, for the mozilla, haste, dpaste, privatebin and pastebin
+    /// backends
+    #[arg(long, value_name = "URL", env = "ZERO_PASTE_URL")]
+    base_url: Option<reqwest::Url>,
+
+    // Only ever set from the config file
+    #[arg(skip)]
+    webhook: Option<WebhookConfig>,
+    #[arg(skip)]
+    pastebin: Option<PastebinConfig>,
+    #[arg(skip)]
+    detector_command: Option<String>,
+    #[arg(skip)]
+    default_lang: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print a completion script for the given shell
+    #[command(hide = true)]
+    Completions { shell: clap_complete::Shell },
+    /// List the supported languages, optionally filtered by a fuzzy query
+    Langs { query: Option<String> },
+    /// Show previously uploaded pastes, oldest first
+    History {
+        /// Only show the most recent N pastes
+        #[arg(short = 'n', long, value_name = "N")]
+        limit: Option<usize>,
+    },
+    /// Delete a paste, for services that allow it
+    Delete {
+        /// URL of the paste, or its deletion token from the history
+        #[arg(value_name = "URL|TOKEN")]
+        target: String,
+        /// Service the paste is on, taken from the history when possible
+        #[arg(short, long, value_enum)]
+        backend: Option<Backend>,
+    },
+    /// Download the raw content of a paste
+    Get {
+        url: reqwest::Url,
+        /// Write the content to a file instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<std::path::PathBuf>,
+        /// Service the paste is on, guessed from the URL by default
+        #[arg(short, long, value_enum)]
+        backend: Option<Backend>,
+        /// Decrypt a paste uploaded with --encrypt
+        #[arg(long, requires = "identity")]
+        decrypt: bool,
+        /// age identity file or SSH private key to decrypt with
+        #[arg(short, long, value_name = "FILE")]
+        identity: Option<std::path::PathBuf>,
+        /// Password of a paste uploaded with --password, taken from the URL's fragment by default
+        #[arg(long, value_name = "PASSWORD", require_equals = true)]
+        password: Option<String>,
+        /// Decode a paste uploaded with --base64
+        #[arg(long)]
+        base64: bool,
+    },
+    /// Check the configuration and whether the paste service can be reached, without uploading
+    Doctor {
+        /// Service to check instead of the configured one
+        #[arg(short, long, value_enum)]
+        backend: Option<Backend>,
+    },
+}
+
+// Accepts any duration, but offers the presets as completions
+#[derive(Clone)]
+struct ExpireParser;
+
+impl TypedValueParser for ExpireParser {
+    type Value = ExpireSpec;
+
+    fn parse_ref(&self, cmd: &clap::Command, arg: Option<&clap::Arg>, value: &std::ffi::OsStr) -> Result<ExpireSpec, clap::Error> {
+        StringValueParser::new().try_map(ExpireSpec::try_from).parse_ref(cmd, arg, value)
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(SUPPORTED_EXPIRE.iter().map(PossibleValue::new)))
+    }
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    // Whether to style output written to a stream, given whether that is a terminal
+    fn enabled(self, terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => terminal && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+        }
+    }
+}
+
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const CYAN: &str = "\x1b[1;36m";
+
+fn paint(text: impl std::fmt::Display, style: &str, enabled: bool) -> String {
+    if enabled {
+        format!("{}{}\x1b[0m", style, text)
+    } else {
+        text.to_string()
+    }
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum Backend {
+    #[default]
+    /// paste.mozilla.org
+    Mozilla,
+    /// GitHub Gist, authenticated with $GITHUB_TOKEN
+    Gist,
+    /// 0x0.st, accepts binary files
+    #[value(name = "0x0")]
+    NullPointer,
+    /// hastebin.com or a self-hosted haste-server
+    Haste,
+    /// sprunge.us
+    Sprunge,
+    /// ix.io
+    #[value(name = "ixio")]
+    IxIo,
+    /// termbin.com, over a plain TCP socket
+    Termbin,
+    /// dpaste.com
+    Dpaste,
+    /// A PrivateBin instance given with --base-url, encrypted before upload
+    #[value(name = "privatebin")]
+    PrivateBin,
+    /// A JSON webhook, set up in the [webhook] table of the config file
+    Webhook,
+    /// pastebin.com, with the developer key in $PASTEBIN_API_KEY or the [pastebin] table of the
+    /// config file
+    #[value(name = "pastebin")]
+    PastebinCom,
+}
+
+impl Backend {
+    fn name(self) -> String {
+        self.to_possible_value().unwrap().get_name().to_string()
+    }
+
+    fn for_host(url: &reqwest::Url) -> Option<Backend> {
+        match url.host_str()? {
+            "paste.mozilla.org" => Some(Backend::Mozilla),
+            "gist.github.com" => Some(Backend::Gist),
+            "0x0.st" => Some(Backend::NullPointer),
+            "hastebin.com" => Some(Backend::Haste),
+            "sprunge.us" => Some(Backend::Sprunge),
+            "ix.io" => Some(Backend::IxIo),
+            "termbin.com" => Some(Backend::Termbin),
+            "dpaste.com" => Some(Backend::Dpaste),
+            "pastebin.com" => Some(Backend::PastebinCom),
+            _ => None,
+        }
+    }
+
+    // The service an existing paste is on: as given, as recorded in the history, guessed from its
+    // host, or else the configured one
+    fn for_paste(url: &reqwest::Url, explicit: Option<Backend>, entry: Option<&HistoryEntry>, args: &Args) -> Backend {
+        explicit
+            .or_else(|| entry.and_then(|entry| entry.backend.as_deref()).and_then(|name| Backend::from_str(name, true).ok()))
+            .or_else(|| Backend::for_host(url))
+            .unwrap_or(args.primary_backend())
+    }
+
+    fn build(self, client: reqwest::Client, args: &Args) -> Result<Box<dyn PasteBackend>, zero_paste::PasteError> {
+        let self_hosted = matches!(self, Backend::Mozilla | Backend::Haste | Backend::Dpaste | Backend::PrivateBin | Backend::PastebinCom);
+        if let Some(url) = args.base_url.as_ref().filter(|_| !self_hosted) {
+            tracing::warn!("the {} backend only talks to its own service, ignoring the base URL {}", self.name(), url);
+        }
+        Ok(match self {
+            Backend::Mozilla => {
+                let mut backend = match &args.base_url {
+                    Some(url) => MozillaPaste::with_base_url(client, url.clone())?,
+                    None => MozillaPaste::new(client),
+                };
+                if let Some(path) = MozillaPaste::session_cache_path().filter(|_| !args.no_cache) {
+                    backend = backend.with_session_cache(path);
+                }
+                Box::new(backend)
+            }
+            Backend::Gist => Box::new(GistBackend::from_env(client, args.visibility() == Visibility::Public)?),
+            Backend::NullPointer => Box::new(NullPointerBackend::new(client)),
+            Backend::Haste => {
+                let host = args.base_url.clone().unwrap_or_else(|| zero_paste::backend::DEFAULT_HASTE_HOST.parse().unwrap());
+                Box::new(HasteBackend::new(client, host)?)
+            }
+            Backend::Sprunge => Box::new(SprungeBackend::new(client)),
+            Backend::IxIo => Box::new(IxIoBackend::new(client)),
+            Backend::Termbin => Box::new(TermbinBackend::new(client)),
+            Backend::Dpaste => match &args.base_url {
+                Some(url) => Box::new(DpasteBackend::with_base_url(client, url.clone())?),
+                None => Box::new(DpasteBackend::new(client)),
+            },
+            Backend::PrivateBin => {
+                let host = args.base_url.clone().ok_or(zero_paste::PasteError::MissingBaseUrl("privatebin"))?;
+                Box::new(PrivateBinBackend::new(client, host)?)
+            }
+            Backend::Webhook => {
+                let config = args.webhook.clone().unwrap_or_default();
+                let url = config.url.ok_or(zero_paste::PasteError::MissingConfig("webhook.url"))?;
+                // Checked when the config file was loaded
+                Box::new(WebhookBackend::new(client, url.parse().unwrap(), config.url_pointer)?)
+            }
+            Backend::PastebinCom => {
+                let config = args.pastebin.clone().unwrap_or_default();
+                let key = |var| std::env::var(var).ok().filter(|key| !key.is_empty());
+                let dev_key = key("PASTEBIN_API_KEY").or(config.api_key).ok_or(zero_paste::PasteError::MissingCredential {
+                    what: "a pastebin.com developer key",
+                    env: "PASTEBIN_API_KEY",
+                    config: "pastebin.api_key",
+                })?;
+                let mut backend = PastebinComBackend::new(client, dev_key, args.visibility());
+                if let Some(user_key) = key("PASTEBIN_USER_KEY").or(config.user_key) {
+                    backend = backend.with_user_key(user_key);
+                }
+                if let Some(url) = &args.base_url {
+                    backend = backend.with_base_url(url.clone())?;
+                }
+                Box::new(backend)
+            }
+        })
+    }
+}
+
+impl Args {
+    // Older releases only took `paste <file> [time] [lang]`, so trailing positionals may still be an expire time and language
+    fn split_legacy_positionals(&mut self) {
+        if self.expire.is_some() {
+            return;
+        }
+        let Some(split) = self.files.iter().skip(1).position(|arg| SUPPORTED_EXPIRE.contains(&arg.as_str())) else {
+            return;
+        };
+
+        let rest = self.files.split_off(split + 1);
+        self.expire = rest[0].parse::<Expire>().ok().map(ExpireSpec::from);
+        match &rest[1..] {
+            [] => {}
+            [lang] if self.lang.is_none() => {
+                if !SUPPORTED_LANG.contains(&lang.as_str()) {
+                    let msg = format!("unsupported language: {}\nSupported languages: {:?}", lang, SUPPORTED_LANG);
+                    Args::command().error(ErrorKind::InvalidValue, msg).exit();
+                }
+                self.lang = Some(lang.clone());
+            }
+            extra => {
+                let msg = format!("unexpected arguments after the expire time: {}", extra.join(" "));
+                Args::command().error(ErrorKind::UnknownArgument, msg).exit();
+            }
+        }
+    }
+}
+
+impl Args {
+    // Whether results printed with `report!` are styled
+    fn color_results(&self) -> bool {
+        use std::io::IsTerminal;
+        let terminal = if self.tee { std::io::stderr().is_terminal() } else { std::io::stdout().is_terminal() };
+        self.color.enabled(terminal)
+    }
+
+    // The backend uploads go to first, and the one the other subcommands use
+    fn primary_backend(&self) -> Backend {
+        self.backend.first().copied().unwrap_or_default()
+    }
+
+    // --public is the older spelling of --visibility public
+    fn visibility(&self) -> Visibility {
+        self.visibility.unwrap_or(if self.public { Visibility::Public } else { Visibility::default() })
+    }
+
+    fn color_stderr(&self) -> bool {
+        use std::io::IsTerminal;
+        self.color.enabled(std::io::stderr().is_terminal())
+    }
+
+    fn client_options(&self) -> ClientOptions {
+        ClientOptions {
+            timeout: (self.timeout > 0).then(|| std::time::Duration::from_secs(self.timeout)),
+            proxy: self.proxy.clone(),
+            headers: self.header.clone(),
+            user_agent: self.user_agent.clone(),
+            max_redirects: self.max_redirects,
+        }
+    }
+
+    // Settings from the config file only fill in what wasn't given on the command line
+    fn apply_config(&mut self, config: Config) -> Result<(), Box<dyn std::error::Error>> {
+        // The command line wins over the environment, which wins over the config file
+        if self.expire.is_none() {
+            if let Some(value) = std::env::var_os("ZERO_PASTE_EXPIRE").filter(|value| !value.is_empty()) {
+                let value = value.to_string_lossy();
+                let expire = ExpireSpec::try_from(value.to_string()).map_err(|e| format!("invalid ZERO_PASTE_EXPIRE `{}`: {}", value, e))?;
+                self.expire = Some(expire);
+            }
+        }
+        self.expire = self.expire.or(config.default_expire);
+        self.default_lang = config.default_lang;
+        if self.backend.is_empty() {
+            for name in config.backends.or(config.backend.map(|name| vec![name])).unwrap_or_default() {
+                let backend = Backend::from_str(&name, true).map_err(|_| format!("unsupported backend `{}` in config", name))?;
+                self.backend.push(backend);
+            }
+        }
+        self.user_agent = self.user_agent.take().or(config.user_agent);
+        self.webhook = config.webhook;
+        self.pastebin = config.pastebin;
+        self.detector_command = config.detector_command.filter(|command| !command.trim().is_empty());
+        if self.base_url.is_none() {
+            if let Some(url) = config.base_url {
+                self.base_url = Some(url.parse().map_err(|e| format!("invalid base_url `{}` in config: {}", url, e))?);
+            }
+        }
+        Ok(())
+    }
+}
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Vec<String> = std::env::args().collect();
-    match &args[..] {
-        [_, ref file, ref time, ref lang] => {
-            if !SUPPORTED_LANG.contains(&lang.as_str()) {
-                println!("Unsupported language: {}", lang);
-                println!("Supported languages: {:?}", SUPPORTED_LANG);
-                return Ok(());
-            }
-            upload_file(file, time, Some(lang.clone())).await?;
-        },
-        [_, ref file,ref time] => {
-            upload_file(file, time, None).await?;
-        },
-        [_, ref file] => {
-            upload_file(file, "once", None).await?;
-        },
-        _ => {
-            println!("Usage: paste <file> [time: once(default), 1h, 1d, 1w, 21d] [lang]");
-            println!("Supported languages: {:?}", SUPPORTED_LANG);
+async fn main() {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if std::env::var_os(CLIPBOARD_OWNER).is_some() {
+        std::process::exit(own_clipboard());
+    }
+
+    let mut args = Args::parse();
+    args.split_legacy_positionals();
+    init_logging(&args);
+
+    let json = args.json;
+    let tee = args.tee;
+    let color = args.color_stderr();
+    let result = match args.command.take() {
+        Some(Command::Completions { shell }) => {
+            clap_complete::generate(shell, &mut Args::command(), "paste", &mut std::io::stdout());
+            Ok(())
+        }
+        Some(Command::Langs { query }) => {
+            print_langs(query.as_deref());
+            Ok(())
+        }
+        Some(Command::History { limit }) => print_history(limit).map_err(Into::into),
+        Some(Command::Delete { target, backend }) => delete(args, &target, backend).await,
+        Some(Command::Get { url, output, backend, decrypt, identity, password, base64 }) => {
+            let identity = identity.filter(|_| decrypt);
+            get(args, &url, output.as_deref(), backend, identity.as_deref(), password, base64).await
+        }
+        Some(Command::Doctor { backend }) => doctor(args, backend).await,
+        None => run(args).await,
+    };
+
+    if let Err(e) = result {
+        // Errors of single uploads were already reported next to their file
+        if let Some(Failed(code)) = e.downcast_ref() {
+            std::process::exit(*code);
+        }
+        if json && tee {
+            eprintln!("{}", serde_json::json!({ "error": e.to_string() }));
+        } else if json {
+            println!("{}", serde_json::json!({ "error": e.to_string() }));
+        } else {
+            eprintln!("{} {}", paint("error:", RED, color), e);
         }
+        std::process::exit(exit_code(e.as_ref()));
+    }
+}
+
+// Some uploads of several failed, with this exit code for the first of them
+#[derive(Debug)]
+struct Failed(i32);
+
+impl std::fmt::Display for Failed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "some uploads failed")
+    }
+}
+
+impl std::error::Error for Failed {}
+
+// A mistake in what was asked for, such as a pattern matching nothing, which exits with 2
+#[derive(Debug)]
+struct Usage(String);
+
+impl std::fmt::Display for Usage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Usage {}
+
+// The exit status documented in --help
+fn exit_code(e: &(dyn std::error::Error + 'static)) -> i32 {
+    use zero_paste::PasteError;
+
+    const OTHER: i32 = 1;
+    const USAGE: i32 = 2;
+    const NETWORK: i32 = 3;
+    const REJECTED: i32 = 4;
+
+    let http = |e: &reqwest::Error| match e.status() {
+        Some(_) => REJECTED,
+        None if e.is_decode() => REJECTED,
+        None => NETWORK,
+    };
+    let io = |e: &std::io::Error| match e.kind() {
+        std::io::ErrorKind::TimedOut | std::io::ErrorKind::ConnectionRefused | std::io::ErrorKind::ConnectionReset | std::io::ErrorKind::ConnectionAborted => NETWORK,
+        std::io::ErrorKind::NotFound | std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::IsADirectory | std::io::ErrorKind::InvalidInput => USAGE,
+        _ => OTHER,
+    };
+
+    if let Some(e) = e.downcast_ref::<PasteError>() {
+        return match e {
+            PasteError::Http(e) => http(e),
+            PasteError::Io(e) | PasteError::ReadInput { source: e, .. } => io(e),
+            PasteError::UploadRejected { .. } | PasteError::RateLimited { .. } | PasteError::CsrfTokenMissing | PasteError::NotFound(_) | PasteError::InvalidResponse(_) => REJECTED,
+            PasteError::UnsupportedLang(_) | PasteError::UnsupportedExpire(_) | PasteError::StdinIsTerminal | PasteError::NotUtf8
+            | PasteError::BinaryContent(_) | PasteError::ArchiveNeedsBinary(_) | PasteError::RemoteTooLarge { .. } | PasteError::InvalidProxy { .. }
+            | PasteError::InvalidHeader { .. } | PasteError::InvalidConfig { .. } | PasteError::InvalidBaseUrl(_) | PasteError::MissingEnv(_)
+            | PasteError::MissingConfig(_) | PasteError::MissingCredential { .. } | PasteError::MissingBaseUrl(_) | PasteError::Unsupported(_)
+            | PasteError::DeleteTokenMissing(_) | PasteError::LinesOutOfRange { .. } | PasteError::InvalidRecipient(_) | PasteError::InvalidSyntax { .. } => USAGE,
+            PasteError::Encryption(_) | PasteError::Decryption(_) | PasteError::InvalidBase64(_) => OTHER,
+        };
+    }
+    if let Some(e) = e.downcast_ref::<reqwest::Error>() {
+        return http(e);
+    }
+    if let Some(e) = e.downcast_ref::<std::io::Error>() {
+        return io(e);
+    }
+    if e.is::<Usage>() {
+        return USAGE;
+    }
+    OTHER
+}
+
+// With --tee stdout carries the content, so results go to stderr instead
+macro_rules! report {
+    ($args:expr, $($arg:tt)*) => {
+        if $args.tee {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+async fn run(mut args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    args.apply_config(Config::load()?)?;
+    let args = &args;
+
+    // A generated key is only known here, so it goes into the fragment of the paste URLs
+    let (encryption, fragment) = match (&args.encrypt, &args.password) {
+        (Some(recipient), _) => (Some(Encryption::Recipient(recipient.clone())), None),
+        (None, Some(Some(password))) => (Some(Encryption::Password(password.clone())), None),
+        (None, Some(None)) => {
+            let key = zero_paste::crypto::generate_password()?;
+            (Some(Encryption::Password(key.clone())), Some(key))
+        }
+        (None, None) => (None, None),
+    };
+    let opts = FileOptions {
+        expire: args.expire.unwrap_or_default(),
+        explicit_expire: args.expire.is_some(),
+        lang: if args.no_highlight { Some("_text".to_string()) } else { args.lang.clone() },
+        default_lang: args.default_lang.clone(),
+        title: args.title.clone(),
+        title_from_first_line: args.title_from_first_line,
+        force: args.force,
+        normalize_eol: args.normalize_eol,
+        trim_trailing: args.trim_trailing,
+        expand_tabs: args.expand_tabs.map(usize::from),
+        wrap: args.wrap.map(usize::from),
+        minify: args.minify,
+        strip_comments: args.strip_comments,
+        syntax_check: args.syntax_check,
+        prepend: args.prepend.clone(),
+        append_text: args.append_text.clone(),
+        lines: args.lines,
+        encryption,
+        with_filename: args.with_filename,
+        max_depth: args.max_depth,
+        no_ignore: args.no_ignore,
+        stdin_name: args.stdin_name.clone(),
+        detector_command: args.detector_command.clone(),
+        base64: args.base64,
+    };
+    let client = zero_paste::client(&args.client_options())?;
+    let backend: Box<dyn PasteBackend> = match &args.backend[..] {
+        [] | [_] => Box::new(Retry::new(args.primary_backend().build(client, args)?, args.retries)),
+        several => {
+            let mut backends = Vec::new();
+            for backend in several {
+                let retry: Box<dyn PasteBackend> = Box::new(Retry::new(backend.build(client.clone(), args)?, args.retries));
+                backends.push((backend.name(), retry));
+            }
+            Box::new(Fallback::new(backends))
+        }
+    };
+    let backend = backend.as_ref();
+
+    // With --cmd the command stands in for the file name
+    let inputs = match &args.cmd {
+        Some(command) => vec![command.clone()],
+        None if args.from_clipboard => vec!["clipboard".to_string()],
+        None if args.append.is_some() => {
+            let buffer = args.append.as_deref().unwrap();
+            zero_paste::append_to_buffer(buffer, &zero_paste::read_stdin()?)?;
+            vec![buffer.to_string_lossy().into_owned()]
+        }
+        None => {
+            let mut inputs = match args.files.is_empty() {
+                true => Vec::new(),
+                false => expand_globs(&args.files)?,
+            };
+            if let Some(manifest) = &args.files_from {
+                inputs.extend(read_manifest(manifest)?);
+            }
+            if inputs.is_empty() {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "the manifest doesn't list any files").into());
+            }
+            inputs
+        }
+    };
+
+    if args.stdin_name.is_some() && !args.from_clipboard && !inputs.iter().any(|file| file == "-") {
+        tracing::warn!("--stdin-name only applies to stdin, which isn't being read");
+    }
+    if args.lang_from.is_some() && !args.from_clipboard && !inputs.iter().any(|file| file == "-") {
+        tracing::warn!("--lang-from only applies to stdin, which isn't being read");
+    }
+
+    if args.dry_run {
+        return dry_run(backend, &inputs, &opts, args).await;
+    }
+    if args.watch {
+        return watch(backend, &inputs, &opts, fragment.as_deref(), args).await;
+    }
+
+    let mut pastes = Vec::new();
+    let mut result = Ok(());
+    // A manifest always gets the per-file report, however many files it lists
+    if let ([file], None) = (&inputs[..], &args.files_from) {
+        let prepared = prepare(backend, file, &opts, args).await?;
+        let paste = with_spinner(args, format!("uploading {}", file), upload(backend, file, &prepared, fragment.as_deref(), args)).await?;
+        if args.json {
+            report!(args, "{}", paste_json(&paste));
+        } else {
+            report_paste(file, &paste, args);
+        }
+        pastes.push(paste);
+    } else {
+        // Files are read one at a time so confirmation prompts don't interleave, then uploaded together
+        let mut prepared = Vec::new();
+        for file in &inputs {
+            prepared.push(prepare(backend, file, &opts, args).await);
+        }
+        let pacer = Pacer::new(std::time::Duration::from_millis(args.delay));
+        let uploads = inputs.iter().zip(prepared).map(|(file, prepared)| async {
+            let prepared = prepared?;
+            pacer.wait().await;
+            Ok::<_, Box<dyn std::error::Error>>(upload(backend, file, &prepared, fragment.as_deref(), args).await?)
+        });
+        // A whole source tree at once would flood the service
+        let uploads = futures::StreamExt::buffered(futures::stream::iter(uploads), args.concurrency.into());
+        let message = format!("uploading {} files", inputs.len());
+        let results: Vec<_> = with_spinner(args, message, futures::StreamExt::collect(uploads)).await;
+        let mut report = Vec::new();
+        let mut failed = None;
+        let mut failed_files = Vec::new();
+        for (file, result) in inputs.iter().zip(results) {
+            match result {
+                Ok(paste) => {
+                    let mut entry = paste_json(&paste);
+                    entry["file"] = file.as_str().into();
+                    report.push(entry);
+                    if !args.json {
+                        report_paste(file, &paste, args);
+                    }
+                    pastes.push(paste);
+                }
+                Err(e) => {
+                    report.push(serde_json::json!({ "file": file, "error": e.to_string() }));
+                    if !args.json {
+                        eprintln!("{}: {} {}", file, paint("error:", RED, args.color_stderr()), e);
+                    }
+                    failed = failed.or(Some(exit_code(e.as_ref())));
+                    failed_files.push(file.as_str());
+                }
+            }
+        }
+        if args.json {
+            let summary = serde_json::json!({ "total": inputs.len(), "uploaded": pastes.len(), "failed": failed_files });
+            report!(args, "{}", serde_json::json!({ "results": report, "summary": summary }));
+        } else if !args.quiet {
+            let outcome = if failed_files.is_empty() { String::new() } else { format!(" ({} failed: {})", failed_files.len(), failed_files.join(", ")) };
+            eprintln!("Uploaded {}/{} files{}", pastes.len(), inputs.len(), outcome);
+        }
+        result = failed.map_or(Ok(()), |code| Err(Failed(code).into()));
+    }
+
+    // Like everything else but the URLs, so `url=$(paste --qr file)` still works
+    if args.qr {
+        for paste in &pastes {
+            match render_qr(paste.url.as_str()) {
+                Ok(qr) => eprintln!("{}", qr),
+                Err(e) => tracing::warn!("could not render a QR code for {}: {}", paste.url, e),
+            }
+        }
+    }
+
+    // The upload already succeeded, so failing to copy only deserves a warning
+    if args.copy && !pastes.is_empty() {
+        let text = pastes.iter().map(|paste| paste.url.as_str()).collect::<Vec<_>>().join("\n");
+        if let Err(e) = copy_to_clipboard(&text) {
+            tracing::warn!("could not copy to the clipboard: {}", e);
+        }
+    }
+
+    // Anyone opening the link, the uploader included, uses up the only view
+    let one_time = pastes.iter().filter(|paste| paste.expires == Some(ExpireSpec::Once)).count();
+    if one_time > 0 {
+        let what = if one_time == 1 { "the paste is one-time and is" } else { "one-time pastes are" };
+        tracing::warn!("{} destroyed by the first view; opening the URL yourself, or a chat app previewing it, uses it up", what);
+    }
+
+    if args.open {
+        for paste in &pastes {
+            if paste.expires == Some(ExpireSpec::Once) && !args.force {
+                tracing::warn!("not opening {}, that would be its only view; pass --force to open it anyway", paste.url);
+                continue;
+            }
+            if let Err(e) = open::that_detached(paste.url.as_str()) {
+                tracing::warn!("could not open {} in a browser: {}", paste.url, e);
+            }
+        }
+    }
+
+    result
+}
+
+// Spaces out the starts of uploads running at the same time by `--delay`
+struct Pacer {
+    delay: std::time::Duration,
+    next: tokio::sync::Mutex<tokio::time::Instant>,
+}
+
+impl Pacer {
+    fn new(delay: std::time::Duration) -> Self {
+        Pacer { delay, next: tokio::sync::Mutex::new(tokio::time::Instant::now()) }
+    }
+
+    async fn wait(&self) {
+        if self.delay.is_zero() {
+            return;
+        }
+        // Held while sleeping, so the uploads that are waiting take their turns in order
+        let mut next = self.next.lock().await;
+        tokio::time::sleep_until(*next).await;
+        *next = tokio::time::Instant::now() + self.delay;
+    }
+}
+
+// Expands patterns like `src/*.rs` for shells that don't, but leaves names of existing files and
+// plain arguments alone. Directories only count when named on their own, not matched by a pattern.
+fn expand_globs(files: &[String]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut expanded = Vec::new();
+    for file in files {
+        if !file.contains(['*', '?', '[']) || zero_paste::is_remote(file) || std::path::Path::new(file).exists() {
+            expanded.push(file.clone());
+            continue;
+        }
+
+        let matches = glob::glob(file).map_err(|e| format!("invalid pattern `{}`: {}", file, e))?;
+        let mut found = false;
+        for path in matches {
+            let path = path?;
+            found = true;
+            if path.is_dir() {
+                tracing::warn!("skipping directory {} matched by `{}`", path.display(), file);
+                continue;
+            }
+            expanded.push(path.to_string_lossy().into_owned());
+        }
+        if !found {
+            return Err(Usage(format!("no files match `{}`", file)).into());
+        }
+    }
+    if expanded.is_empty() {
+        return Err(Usage("the patterns only matched directories, nothing to upload".into()).into());
+    }
+    Ok(expanded)
+}
+
+// Paths in the manifest are taken as they are, relative to the working directory like those on
+// the command line. Missing files are only warned about here; their uploads fail on their own.
+fn read_manifest(manifest: &std::path::Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string(manifest).map_err(|e| std::io::Error::new(e.kind(), format!("could not read {}: {}", manifest.display(), e)))?;
+    let mut files = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let file = line.trim();
+        if file.is_empty() || file.starts_with('#') {
+            continue;
+        }
+        if !zero_paste::is_remote(file) && !std::path::Path::new(file).exists() {
+            tracing::warn!("{}:{}: {} doesn't exist", manifest.display(), i + 1, file);
+        }
+        files.push(file.to_string());
+    }
+    Ok(files)
+}
+
+// The spinner currently on screen, which log lines have to be printed around
+static SPINNER: std::sync::Mutex<Option<indicatif::ProgressBar>> = std::sync::Mutex::new(None);
+
+// Shows a spinner on stderr while `future` runs, unless nobody is there to see it
+async fn with_spinner<T>(args: &Args, message: String, future: impl std::future::Future<Output = T>) -> T {
+    use std::io::IsTerminal;
+    if args.quiet || args.json || !std::io::stderr().is_terminal() {
+        return future.await;
+    }
+
+    let spinner = indicatif::ProgressBar::new_spinner().with_message(message);
+    spinner.enable_steady_tick(std::time::Duration::from_millis(100));
+    *SPINNER.lock().unwrap() = Some(spinner.clone());
+    let result = future.await;
+    SPINNER.lock().unwrap().take();
+    spinner.finish_and_clear();
+    result
+}
+
+async fn upload(backend: &dyn PasteBackend, file: &str, prepared: &PreparedPaste, fragment: Option<&str>, args: &Args) -> Result<PasteResult, zero_paste::PasteError> {
+    if args.dedupe {
+        if let Some(paste) = find_duplicate(file, prepared, args)? {
+            return Ok(paste);
+        }
+    }
+
+    let mut paste = zero_paste::upload_prepared(backend, prepared).await?;
+    if fragment.is_some() {
+        paste.url.set_fragment(fragment);
+    }
+    record_history(args, file, prepared.opts.expire, &paste);
+    Ok(paste)
+}
+
+fn find_duplicate(file: &str, prepared: &PreparedPaste, args: &Args) -> Result<Option<PasteResult>, zero_paste::PasteError> {
+    let Some(sha256) = prepared.sha256()? else {
+        tracing::debug!("{} can only be hashed while it's uploaded, so it can't be looked up in the history", file);
+        return Ok(None);
+    };
+    let history = HistoryEntry::load()?;
+    let backends = if args.backend.is_empty() { vec![Backend::default()] } else { args.backend.clone() };
+    let entry = backends.iter().find_map(|backend| HistoryEntry::find_duplicate(&history, &sha256, &backend.name(), prepared.opts.expire));
+    let Some(entry) = entry else {
+        return Ok(None);
+    };
+    let (Ok(url), Ok(expires)) = (entry.url.parse(), zero_paste::parse_expiry(&entry.expires)) else {
+        return Ok(None);
+    };
+
+    tracing::info!("{} is unchanged since {}, reusing its paste", file, entry.timestamp);
+    let bytes = prepared.content.len();
+    Ok(Some(PasteResult { url, lang: entry.lang.clone(), expires, delete_token: entry.delete_token.clone(), bytes, sha256, backend: entry.backend.clone() }))
+}
+
+// Editors tend to write a file several times per save, so changes are collected until it's quiet
+const WATCH_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(300);
+
+async fn watch(backend: &dyn PasteBackend, inputs: &[String], opts: &FileOptions, fragment: Option<&str>, args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    use notify::Watcher;
+
+    if inputs.iter().any(|file| file == "-") {
+        return Err(Usage("--watch needs files to watch, it can't be used with stdin".into()).into());
+    }
+    if let Some(url) = inputs.iter().find(|file| zero_paste::is_remote(file)) {
+        return Err(Usage(format!("--watch needs files to watch, it can't watch {}", url)).into());
+    }
+    let paths = inputs.iter()
+        .map(|file| std::fs::canonicalize(file).map_err(|source| zero_paste::PasteError::ReadInput { path: file.into(), source }))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })?;
+    // Saving by writing a new file and renaming it over the old one is common, so watch the
+    // directories rather than the files themselves
+    let dirs: std::collections::BTreeSet<_> = paths.iter().filter_map(|path| path.parent()).collect();
+    for dir in dirs {
+        watcher.watch(dir, notify::RecursiveMode::NonRecursive)?;
+    }
+
+    let mut pastes = Vec::new();
+    for file in inputs {
+        watch_upload(backend, file, opts, fragment, args, &mut pastes).await;
+    }
+    tracing::info!("watching for changes, press Ctrl-C to stop");
+
+    let changed_files = |event: notify::Result<notify::Event>, changed: &mut std::collections::BTreeSet<usize>| match event {
+        Ok(event) if event.kind.is_create() || event.kind.is_modify() => {
+            changed.extend(event.paths.iter().filter_map(|path| paths.iter().position(|watched| watched == path)));
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("error while watching: {}", e),
+    };
+    let mut interrupted = std::pin::pin!(tokio::signal::ctrl_c());
+    loop {
+        let event = tokio::select! {
+            event = rx.recv() => event,
+            _ = &mut interrupted => break,
+        };
+        let Some(event) = event else { break };
+
+        let mut changed = std::collections::BTreeSet::new();
+        changed_files(event, &mut changed);
+        while let Ok(Some(event)) = tokio::time::timeout(WATCH_DEBOUNCE, rx.recv()).await {
+            changed_files(event, &mut changed);
+        }
+        for i in changed {
+            watch_upload(backend, &inputs[i], opts, fragment, args, &mut pastes).await;
+        }
+    }
+
+    if !args.json && !args.quiet && !pastes.is_empty() {
+        eprintln!("\n{} pastes this session:", pastes.len());
+        for (file, url) in &pastes {
+            eprintln!("  {}: {}", file, url);
+        }
+    }
+    Ok(())
+}
+
+// A failed upload, like a file caught halfway through a save, shouldn't end the watch
+async fn watch_upload(backend: &dyn PasteBackend, file: &str, opts: &FileOptions, fragment: Option<&str>, args: &Args, pastes: &mut Vec<(String, reqwest::Url)>) {
+    let result = async {
+        let prepared = prepare(backend, file, opts, args).await?;
+        Ok::<_, Box<dyn std::error::Error>>(upload(backend, file, &prepared, fragment, args).await?)
+    };
+    let paste = match result.await {
+        Ok(paste) => paste,
+        Err(e) => {
+            tracing::error!("{}: {}", file, e);
+            return;
+        }
+    };
+
+    if args.json {
+        let mut entry = paste_json(&paste);
+        entry["file"] = file.into();
+        println!("{}", entry);
+    } else {
+        if !args.quiet && pastes.iter().any(|(earlier, _)| earlier == file) {
+            eprintln!("{} changed, new paste:", file);
+        } else if !args.quiet {
+            eprintln!("{}:", file);
+        }
+        match &args.format {
+            Some(template) => println!("{}", template.render(file, &paste)),
+            None => println!("{}", paste.url),
+        }
+    }
+    pastes.push((file.to_string(), paste.url));
+}
+
+// Prints the fields exactly as the backend would submit them, but sends nothing
+async fn dry_run(backend: &dyn PasteBackend, inputs: &[String], opts: &FileOptions, args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let mut report = Vec::new();
+    for file in inputs {
+        let prepared = read_input(backend, file, opts, args).await?;
+        let fields = backend.fields(&prepared.opts);
+        if args.json {
+            let fields: serde_json::Map<_, _> = fields.into_iter().map(|(name, value)| (name.to_string(), value.into())).collect();
+            report.push(serde_json::json!({ "file": file, "size": prepared.content.len(), "fields": fields }));
+        } else {
+            println!("{}: {} bytes of content", file, prepared.content.len());
+            for (name, value) in fields {
+                println!("  {}: {}", name, value);
+            }
+        }
+    }
+    if args.json {
+        println!("{}", serde_json::Value::Array(report));
     }
     Ok(())
 }
 
-async fn upload_file(file: &str, time: &str, lang: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
-    if !SUPPORTED_EXPIRE.contains(&time) {
-        println!("Unsupported expire time: {}", time);
-        println!("Supported expire time: {:?}", SUPPORTED_EXPIRE);
+// Either the file, the clipboard, or the output of --cmd highlighted as a console session
+async fn read_input(backend: &dyn PasteBackend, file: &str, opts: &FileOptions, args: &Args) -> Result<PreparedPaste, Box<dyn std::error::Error>> {
+    let opts = &lang_from(file, opts, args);
+    if args.from_clipboard {
+        let content = paste_from_clipboard()?.into_bytes();
+        if args.tee {
+            write_stdout(&content)?;
+        }
+        // Like stdin, there's no file name to go by unless one is given
+        return Ok(zero_paste::prepare_content(backend, content, opts.stdin_name.as_deref(), opts)?);
+    }
+    if zero_paste::is_remote(file) {
+        return fetch_input(backend, file, opts, args).await;
+    }
+    let Some(command) = &args.cmd else {
+        if args.tee {
+            return tee_input(backend, file, opts);
+        }
+        return Ok(zero_paste::prepare_file(backend, file, opts)?);
+    };
+    let opts = FileOptions {
+        lang: opts.lang.clone().or(Some("console".to_string())),
+        title: opts.title.clone().or_else(|| (!opts.title_from_first_line).then(|| command.clone())),
+        ..opts.clone()
+    };
+    let output = zero_paste::run_command(command).await?;
+    if args.tee {
+        write_stdout(&output)?;
+    }
+    Ok(zero_paste::prepare_content(backend, output, None, &opts)?)
+}
+
+// Like --stdin-name, --lang-from only names what's piped in or pasted, so files keep their own
+// detection
+fn lang_from(file: &str, opts: &FileOptions, args: &Args) -> FileOptions {
+    let Some(path) = args.lang_from.as_ref().filter(|_| opts.lang.is_none() && (file == "-" || args.from_clipboard)) else {
+        return opts.clone();
+    };
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let lang = zero_paste::map_filename_to_lang(name);
+    if lang.is_none() {
+        tracing::warn!("no language is known for {}, detecting it from the content", path.display());
+    }
+    FileOptions { lang, ..opts.clone() }
+}
+
+// A URL stands in for a file named like the last part of its path, which picks the language
+async fn fetch_input(backend: &dyn PasteBackend, file: &str, opts: &FileOptions, args: &Args) -> Result<PreparedPaste, Box<dyn std::error::Error>> {
+    let url: reqwest::Url = file.parse().map_err(|e| format!("invalid URL {}: {}", file, e))?;
+    let client = zero_paste::client(&args.client_options())?;
+    let limit = (!args.yes).then_some(args.max_size);
+    let content = zero_paste::fetch_url(&client, &url, limit, backend.supports_binary() || opts.base64).await?;
+    tracing::info!("fetched {} from {}", format_size(content.len() as u64), url);
+    if args.tee {
+        write_stdout(&content)?;
+    }
+
+    let name = url.path_segments().and_then(|mut segments| segments.next_back()).filter(|name| !name.is_empty());
+    let title = opts.title.clone().or_else(|| (!opts.title_from_first_line).then(|| url.to_string()));
+    let opts = FileOptions { title, ..opts.clone() };
+    Ok(zero_paste::prepare_content(backend, content, name, &opts)?)
+}
+
+// Stdin can only be read once, so it's echoed from memory rather than read again
+fn tee_input(backend: &dyn PasteBackend, file: &str, opts: &FileOptions) -> Result<PreparedPaste, Box<dyn std::error::Error>> {
+    if file == "-" {
+        let content = zero_paste::read_stdin()?;
+        write_stdout(&content)?;
+        return Ok(zero_paste::prepare_content(backend, content, opts.stdin_name.as_deref(), opts)?);
+    }
+    let mut stdout = std::io::stdout().lock();
+    let read = |source| zero_paste::PasteError::ReadInput { path: file.into(), source };
+    std::io::copy(&mut std::fs::File::open(file).map_err(read)?, &mut stdout).map_err(read)?;
+    std::io::Write::flush(&mut stdout)?;
+    Ok(zero_paste::prepare_file(backend, file, opts)?)
+}
+
+fn write_stdout(content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(content)?;
+    stdout.flush()
+}
+
+async fn prepare(backend: &dyn PasteBackend, file: &str, opts: &FileOptions, args: &Args) -> Result<PreparedPaste, Box<dyn std::error::Error>> {
+    let mut prepared = read_input(backend, file, opts, args).await?;
+    confirm_size(file, &prepared, args)?;
+    if args.interactive {
+        ask_missing(file, &mut prepared.opts, args)?;
+    }
+    Ok(prepared)
+}
+
+fn confirm_size(file: &str, prepared: &PreparedPaste, args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::IsTerminal;
+
+    let size = prepared.content.len();
+    if size <= args.max_size || args.yes {
         return Ok(());
     }
 
-    let path = std::path::Path::new(file);
-    let file_content = std::fs::read_to_string(file)?;
-    let lang = lang.or(
-        path.file_name()
-        .and_then(|file| file.to_str())
-        .and_then(|file| map_filename_to_lang(file))
-    ).unwrap_or("_code".to_string());
-
-    let client = reqwest::ClientBuilder::new()
-        .cookie_store(true)
-        .redirect(reqwest::redirect::Policy::limited(1024))
-        .build()?;
-
-    let res = client.get(BASE_URL)
-        .send()
-        .await?;
-
-    let html = res.text().await?;
-    let document = dom_query::Document::from(html);
-
-    let token = document.select("input[name=csrfmiddlewaretoken]").attr("value").unwrap().to_string();
-
-    let mut form = std::collections::HashMap::new();
-    form.insert("csrfmiddlewaretoken", token);
-    form.insert("content", file_content);
-    form.insert("expires", match time {
-        "once" => "onetime",
-        "1h" => "3600",
-        "1d" => "86400",
-        "1w" => "604800",
-        "21d" => "2073600",
-        _ => "onetime",
-    }.to_string());
-    form.insert("lexer", lang);
-    form.insert("title", "".to_string());
-
-
-    let res = client.post(BASE_URL)
-        .header("Content-Type", "application/x-www-form-urlencoded")
-        .header("Referer", BASE_URL)
-        .header("Origin", BASE_URL)
-        .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/129.0.0.0 Safari/537.36")
-        .form(&form)
-        .send()
-        .await?;
-
-    println!("Paste url: {}", res.url().to_string());
+    let name = if file == "-" { "stdin" } else { file };
+    // Of piped input too large to hold, only the start is known
+    let over = if matches!(prepared.content, zero_paste::PasteContent::Stdin { .. }) { "over " } else { "" };
+    tracing::warn!("{} is {}{}, larger than the {} limit", name, over, format_size(size), format_size(args.max_size));
+    // Without someone to answer, refuse rather than upload something surprising
+    let interactive = !args.json && std::io::stdin().is_terminal() && std::io::stderr().is_terminal();
+    if !interactive {
+        return Err(Usage(format!("refusing to upload {}; pass --yes or raise --max-size", name)).into());
+    }
 
+    eprint!("Upload it anyway? [y/N] ");
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => Ok(()),
+        _ => Err(format!("not uploading {}", name).into()),
+    }
+}
+
+// Options given on the command line or in the config aren't asked for again
+fn ask_missing(file: &str, opts: &mut zero_paste::UploadOptions, args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::IsTerminal;
+
+    // Piped content takes stdin, so there'd be nothing to read the answers from
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        tracing::debug!("no terminal to ask on, using the defaults for {}", file);
+        return Ok(());
+    }
+    let name = if file == "-" { "stdin" } else { file };
+
+    if args.expire.is_none() {
+        let current = SUPPORTED_EXPIRE.iter().position(|expire| expire.parse().ok() == Some(opts.expire)).unwrap_or_default();
+        let choice = dialoguer::Select::new()
+            .with_prompt(format!("Expire time for {}", name))
+            .items(SUPPORTED_EXPIRE)
+            .default(current)
+            .interact()?;
+        opts.expire = SUPPORTED_EXPIRE[choice].parse()?;
+        opts.explicit_expire = true;
+    }
+    if args.lang.is_none() && !args.no_highlight {
+        opts.lang = dialoguer::Input::new()
+            .with_prompt("Language")
+            .default(opts.lang.clone())
+            .validate_with(|lang: &String| match SUPPORTED_LANG.contains(&lang.as_str()) {
+                true => Ok(()),
+                false => Err("unsupported language, `paste langs` lists them"),
+            })
+            .interact_text()?;
+    }
+    if args.title.is_none() {
+        opts.title = dialoguer::Input::new()
+            .with_prompt("Title")
+            .default(opts.title.clone())
+            .show_default(!opts.title.is_empty())
+            .allow_empty(true)
+            .interact_text()?;
+    }
     Ok(())
 }
 
+// Plain bytes or a binary K, M or G suffix
+fn parse_size(value: &str) -> Result<u64, String> {
+    let upper = value.trim().to_uppercase();
+    let digits = upper.trim_end_matches(['B', 'I']);
+    let (number, shift) = match digits.chars().last() {
+        Some('K') => (&digits[..digits.len() - 1], 10),
+        Some('M') => (&digits[..digits.len() - 1], 20),
+        Some('G') => (&digits[..digits.len() - 1], 30),
+        _ => (digits, 0),
+    };
+    let number: u64 = number.trim().parse().map_err(|_| format!("invalid size `{}`, expected e.g. 512K or 4M", value))?;
+    number.checked_mul(1 << shift).ok_or_else(|| format!("size `{}` is too large", value))
+}
+
+// Checked up front, so a typo in a placeholder fails before anything is uploaded
+fn parse_template(value: &str) -> Result<Template, String> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let Some((name, rest)) = chars.as_str().split_once('}') else {
+                    return Err("unclosed `{`; write {{ for a literal brace".to_string());
+                };
+                let Some((_, field)) = TEMPLATE_FIELDS.iter().find(|(known, _)| *known == name) else {
+                    let known: Vec<_> = TEMPLATE_FIELDS.iter().map(|(known, _)| format!("{{{}}}", known)).collect();
+                    return Err(format!("unknown placeholder {{{}}}, expected one of {}", name, known.join(", ")));
+                };
+                pieces.push(Piece::Text(std::mem::take(&mut text)));
+                pieces.push(Piece::Field(*field));
+                chars = rest.chars();
+            }
+            '}' => return Err("unmatched `}`; write }} for a literal brace".to_string()),
+            _ => text.push(c),
+        }
+    }
+    pieces.push(Piece::Text(text));
+    Ok(Template(pieces))
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{} bytes", bytes),
+        1024..1048576 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / 1048576.0),
+    }
+}
+
+// Warnings and notes keep their familiar `warning:` and `note:` prefixes; -v and -vv add HTTP details
+fn init_logging(args: &Args) {
+    let level = match (args.quiet, args.verbose) {
+        (true, _) => tracing::Level::ERROR,
+        (false, 0) => tracing::Level::INFO,
+        (false, 1) => tracing::Level::DEBUG,
+        (false, _) => tracing::Level::TRACE,
+    };
+    let targets = tracing_subscriber::filter::Targets::new()
+        .with_target("zero_paste", level)
+        .with_target("paste", level);
+
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+    let format = tracing_subscriber::fmt::layer().event_format(Prefixed { color: args.color_stderr() }).with_writer(|| LogWriter);
+    tracing_subscriber::registry().with(format).with(targets).init();
+}
+
+// Stderr, but with any spinner hidden while a log line is written
+struct LogWriter;
 
-fn map_filename_to_lang(file: &str) -> Option<String> {
-    // Convert the file name to lowercase for case-insensitive matching
-    let file_lower = file.to_lowercase();
-
-    // Handle special cases that don't follow the regular file extension pattern
-    let special_cases = match file_lower.as_str() {
-        "dockerfile" => Some("docker"),
-        "makefile" => Some("make"),
-        "cmakelists.txt" => Some("cmake"),
-        "nginx.conf" => Some("nginx"),
-        f if f.contains("nginx") => Some("nginx"),
-        _ => None,
-    };
-
-    if special_cases.is_some() {
-        return special_cases.map(|l| l.to_string());
-    }
-
-    // Create a regex to extract the file extension for standard cases
-    let re = Regex::new(r"\.([a-zA-Z0-9+_-]+)$").unwrap();
-
-    // Check if the file matches the regex and capture the extension
-    if let Some(caps) = re.captures(&file_lower) {
-        if let Some(ext) = caps.get(1) {
-            let ext = ext.as_str();
-            // Map file extension to programming languages
-            let lang = match ext {
-                "txt" => Some("_text"),
-                "md" => Some("_markdown"),
-                "rst" => Some("_rst"),
-                "sh" => Some("bash"),
-                "bat" => Some("bat"),
-                "c" => Some("c"),
-                "lisp" | "lsp" | "cl" => Some("common-lisp"),
-                "cpp" | "cc" | "cxx" | "hpp" | "hxx" | "inc" | "hh" | "h" => Some("cpp"),
-                "cs" => Some("csharp"),
-                "cmake" | "in" => Some("cmake"),
-                "css" => Some("css"),
-                "dart" => Some("dart"),
-                "patch" | "diff" => Some("diff"),
-                "elixir" | "ex" | "exs" => Some("elixir"),
-                "erl" => Some("erlang"),
-                "go" => Some("go"),
-                "hbs" => Some("handlebars"),
-                "hs" => Some("haskell"),
-                "html" | "htm" | "shtm" | "shtml" => Some("html"),
-                "ini" => Some("ini"),
-                "java" => Some("java"),
-                "js" | "ts" => Some("js"),
-                "json" | "jsonl" => Some("json"),
-                "tsx" | "jsx" => Some("jsx"),
-                "kt" | "kts" => Some("kotlin"),
-                "lua" => Some("lua"),
-                "m" | "mm" => Some("objective-c"),
-                "pl" => Some("perl"),
-                "php" => Some("php"),
-                "py" => Some("python"),
-                "rb" => Some("rb"),
-                "rs" => Some("rust"),
-                "sass" => Some("sass"),
-                "scss" => Some("scss"),
-                "sol" => Some("sol"),
-                "sql" => Some("sql"),
-                "swift" => Some("swift"),
-                "tex" => Some("tex"),
-                "typoscript" => Some("typoscript"),
-                "vim" => Some("vim"),
-                "xml" => Some("xml"),
-                "xsl" | "xslt" => Some("xslt"),
-                "yml" | "yaml" => Some("yaml"),
-                _ => None,
+impl std::io::Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &*SPINNER.lock().unwrap() {
+            Some(spinner) => spinner.suspend(|| std::io::stderr().write(buf)),
+            None => std::io::stderr().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stderr().flush()
+    }
+}
+
+struct Prefixed {
+    color: bool,
+}
+
+impl<S, N> tracing_subscriber::fmt::FormatEvent<S, N> for Prefixed
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    N: for<'a> tracing_subscriber::fmt::FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &tracing_subscriber::fmt::FmtContext<'_, S, N>,
+        mut writer: tracing_subscriber::fmt::format::Writer<'_>,
+        event: &tracing::Event<'_>,
+    ) -> std::fmt::Result {
+        let (prefix, style) = match *event.metadata().level() {
+            tracing::Level::ERROR => ("error:", RED),
+            tracing::Level::WARN => ("warning:", YELLOW),
+            tracing::Level::INFO => ("note:", CYAN),
+            tracing::Level::DEBUG => ("debug:", DIM),
+            tracing::Level::TRACE => ("trace:", DIM),
+        };
+        write!(writer, "{} ", paint(prefix, style, self.color))?;
+        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
+
+// The paste exists either way, so a history that can't be written only deserves a warning
+fn record_history(args: &Args, file: &str, expire: ExpireSpec, paste: &PasteResult) {
+    let backend = paste.backend.clone().unwrap_or_else(|| args.primary_backend().name());
+    if let Err(e) = HistoryEntry::new(file, &backend, expire, paste).append() {
+        tracing::warn!("could not record the paste in the history: {}", e);
+    }
+}
+
+// Prints the settings in effect, then checks the proxy and the paste form
+async fn doctor(mut args: Args, backend: Option<Backend>) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::path();
+    args.apply_config(Config::load()?)?;
+    let backend = backend.unwrap_or(args.primary_backend());
+    let path = |path: Option<std::path::PathBuf>| path.map_or("none".to_string(), |path| path.display().to_string());
+
+    let found = if config.as_deref().is_some_and(std::path::Path::exists) { "" } else { " (not found)" };
+    println!("config file:   {}{}", path(config), found);
+    println!("backend:       {}", backend.name());
+    if let Some(url) = &args.base_url {
+        println!("base url:      {}", url);
+    }
+    println!("expire:        {}", args.expire.unwrap_or_default());
+    println!("lang:          {}", args.lang.as_deref().unwrap_or("detected"));
+    println!("timeout:       {}", if args.timeout > 0 { format!("{}s", args.timeout) } else { "none".to_string() });
+    println!("retries:       {}", args.retries);
+    println!("concurrency:   {}{}", args.concurrency, if args.delay > 0 { format!(", {}ms apart", args.delay) } else { String::new() });
+    println!("user agent:    {}", args.user_agent.as_deref().unwrap_or(concat!("zero-paste/", env!("CARGO_PKG_VERSION"))));
+    let (https, http) = match &args.proxy {
+        Some(proxy) => (Some(proxy.clone()), Some(proxy.clone())),
+        None => (env_proxy("https"), env_proxy("http")),
+    };
+    match (&https, &http) {
+        (https, http) if https == http => println!("proxy:         {}", https.as_deref().unwrap_or("none")),
+        (https, http) => println!("proxy:         {} for https, {} for http", https.as_deref().unwrap_or("none"), http.as_deref().unwrap_or("none")),
+    }
+    println!("session cache: {}", path(MozillaPaste::session_cache_path().filter(|_| !args.no_cache)));
+    println!("history:       {}", path(HistoryEntry::path()));
+    println!();
+
+    let mut failed = None;
+    let mut check = |name: &str, result: Result<String, Box<dyn std::error::Error>>| match result {
+        Ok(detail) => println!("{} {}: {}", paint("ok", CYAN, args.color_results()), name, detail),
+        Err(e) => {
+            println!("{} {}: {}", paint("failed", RED, args.color_results()), name, e);
+            failed = failed.or(Some(exit_code(e.as_ref())));
+        }
+    };
+
+    // Every backend is served over https, unless --base-url points elsewhere
+    let proxy = match &args.base_url {
+        Some(url) if url.scheme() == "http" => http,
+        _ => https,
+    };
+    if let Some(proxy) = &proxy {
+        check("proxy", check_proxy(proxy).await);
+    }
+    let client = zero_paste::client(&args.client_options())?;
+    match backend {
+        Backend::Mozilla => {
+            // Never the cached session, which would hide whether the form page still works
+            let form = match &args.base_url {
+                Some(url) => MozillaPaste::with_base_url(client, url.clone())?,
+                None => MozillaPaste::new(client),
             };
-            return lang.map(|l| l.to_string());
+            let token = form.fetch_token().await.map(|token| format!("found a CSRF token of {} characters", token.len()));
+            check("paste form", token.map_err(Into::into));
+        }
+        _ => println!("note: only the mozilla backend has a form to check"),
+    }
+
+    failed.map_or(Ok(()), |code| Err(Failed(code).into()))
+}
+
+// A proxy that accepts connections may still refuse to forward them, but this tells the two apart
+async fn check_proxy(proxy: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let url = reqwest::Url::parse(proxy).map_err(|e| format!("invalid proxy URL: {}", e))?;
+    let host = url.host_str().ok_or("the proxy URL has no host")?;
+    let port = url.port_or_known_default().unwrap_or(1080);
+    let connect = tokio::net::TcpStream::connect((host, port));
+    match tokio::time::timeout(std::time::Duration::from_secs(5), connect).await {
+        Ok(Ok(_)) => Ok(format!("{}:{} accepts connections", host, port)),
+        Ok(Err(e)) => Err(e.into()),
+        Err(_) => Err(format!("no answer from {}:{} within 5s", host, port).into()),
+    }
+}
+
+async fn delete(mut args: Args, target: &str, backend: Option<Backend>) -> Result<(), Box<dyn std::error::Error>> {
+    args.apply_config(Config::load()?)?;
+
+    let history = HistoryEntry::load()?;
+    let entry = HistoryEntry::find(&history, target);
+    let url: reqwest::Url = match entry {
+        Some(entry) => entry.url.parse()?,
+        None => target.parse().map_err(|_| format!("{} is neither a paste URL nor a deletion token from the history", target))?,
+    };
+
+    let backend = Backend::for_paste(&url, backend, entry, &args);
+    let client = zero_paste::client(&args.client_options())?;
+    backend.build(client, &args)?.delete(&url, entry.and_then(|entry| entry.delete_token.as_deref())).await?;
+
+    if !args.quiet {
+        eprintln!("Deleted {}", url);
+    }
+    Ok(())
+}
+
+async fn get(
+    mut args: Args,
+    url: &reqwest::Url,
+    output: Option<&std::path::Path>,
+    backend: Option<Backend>,
+    identity: Option<&std::path::Path>,
+    password: Option<String>,
+    base64: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    args.apply_config(Config::load()?)?;
+    let history = HistoryEntry::load()?;
+    let backend = Backend::for_paste(url, backend, HistoryEntry::find(&history, url.as_str()), &args);
+    let client = zero_paste::client(&args.client_options())?;
+    let mut content = backend.build(client, &args)?.fetch(url).await?;
+    if let Some(identity) = identity {
+        content = zero_paste::crypto::decrypt(&content, identity)?;
+    } else if zero_paste::crypto::is_password_encrypted(&content) {
+        let password = password.or_else(|| url.fragment().map(str::to_string))
+            .ok_or_else(|| Usage("the paste is encrypted with a password; pass it with --password=PASSWORD".into()))?;
+        content = zero_paste::crypto::decrypt_with_password(&content, &password)?;
+    }
+    if base64 {
+        content = zero_paste::decode_base64(&content)?;
+    }
+
+    match output {
+        Some(path) => std::fs::write(path, &content)?,
+        None => std::io::stdout().write_all(&content)?,
+    }
+    Ok(())
+}
+
+fn print_history(limit: Option<usize>) -> Result<(), zero_paste::PasteError> {
+    let entries = HistoryEntry::load()?;
+    let skip = limit.map_or(0, |limit| entries.len().saturating_sub(limit));
+    for entry in &entries[skip..] {
+        let file = entry.file.as_deref().unwrap_or("stdin");
+        println!("{}  {}  {} ({}, expires {})", entry.timestamp, entry.url, file, entry.lang, entry.expires);
+    }
+    Ok(())
+}
+
+fn print_langs(query: Option<&str>) {
+    // Fuzzy in the simplest sense: the query's characters appear in order, so `py` finds ipythonconsole
+    let matches = |lang: &str| {
+        let mut chars = lang.chars();
+        query.unwrap_or_default().to_lowercase().chars().all(|c| chars.any(|l| l == c))
+    };
+    let mut langs: Vec<&str> = SUPPORTED_LANG.iter().copied().filter(|lang| matches(lang)).collect();
+    langs.sort_unstable();
+
+    if langs.is_empty() {
+        eprintln!("no languages match {:?}", query.unwrap_or_default());
+        return;
+    }
+
+    // Fill columns top to bottom like ls, fitting the terminal width
+    let width = langs.iter().map(|lang| lang.len()).max().unwrap_or_default() + 2;
+    let term_width = std::env::var("COLUMNS").ok().and_then(|cols| cols.parse().ok()).unwrap_or(80);
+    let columns = (term_width / width).max(1);
+    let rows = langs.len().div_ceil(columns);
+    for row in 0..rows {
+        let line: String = langs.iter()
+            .skip(row)
+            .step_by(rows)
+            .map(|lang| format!("{:<width$}", lang, width = width))
+            .collect();
+        println!("{}", line.trim_end());
+    }
+}
+
+// The URL alone goes to stdout, so `url=$(paste file)` works; the details go to stderr
+fn report_paste(file: &str, paste: &PasteResult, args: &Args) {
+    if !args.quiet && !args.raw {
+        eprintln!("{}: {}", if file == "-" { "stdin" } else { file }, paste_summary(paste));
+    }
+    match &args.format {
+        Some(template) => report!(args, "{}", template.render(file, paste)),
+        None => report!(args, "{}", paint(paste.url.as_str(), BOLD, args.color_results())),
+    }
+}
+
+const TEMPLATE_FIELDS: &[(&str, Field)] = &[
+    ("url", Field::Url),
+    ("file", Field::File),
+    ("lang", Field::Lang),
+    ("expires", Field::Expires),
+    ("expires_at", Field::ExpiresAt),
+    ("bytes", Field::Bytes),
+    ("sha256", Field::Sha256),
+];
+
+/// A `--format` template, split into its text and placeholders.
+#[derive(Debug, Clone)]
+struct Template(Vec<Piece>);
+
+#[derive(Debug, Clone)]
+enum Piece {
+    Text(String),
+    Field(Field),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    Url,
+    File,
+    Lang,
+    Expires,
+    ExpiresAt,
+    Bytes,
+    Sha256,
+}
+
+impl Template {
+    fn render(&self, file: &str, paste: &PasteResult) -> String {
+        self.0.iter().map(|piece| match piece {
+            Piece::Text(text) => text.clone(),
+            Piece::Field(Field::Url) => paste.url.to_string(),
+            Piece::Field(Field::File) => if file == "-" { "stdin" } else { file }.to_string(),
+            Piece::Field(Field::Lang) => paste.lang.clone(),
+            Piece::Field(Field::Expires) => zero_paste::format_expiry(paste.expires),
+            // Empty for one-time pastes and pastes without an expire time
+            Piece::Field(Field::ExpiresAt) => expires_at(paste).map_or(String::new(), |at| humantime::format_rfc3339_seconds(at).to_string()),
+            Piece::Field(Field::Bytes) => paste.bytes.to_string(),
+            Piece::Field(Field::Sha256) => paste.sha256.clone(),
+        }).collect()
+    }
+}
+
+fn paste_summary(paste: &PasteResult) -> String {
+    let expires = match expires_at(paste) {
+        Some(at) => chrono::DateTime::<chrono::Local>::from(at).format("expires at %Y-%m-%d %H:%M local").to_string(),
+        None if paste.expires == Some(ExpireSpec::Once) => "expires after first view".to_string(),
+        None => "no expire time".to_string(),
+    };
+    format!("lang: {}, {}", paste.lang, expires)
+}
+
+fn paste_json(paste: &PasteResult) -> serde_json::Value {
+    let mut json = serde_json::json!({
+        "url": paste.url.as_str(),
+        "lang": paste.lang,
+        "expires": zero_paste::format_expiry(paste.expires),
+        "bytes": paste.bytes,
+        "sha256": paste.sha256,
+        "one_time": paste.expires == Some(ExpireSpec::Once),
+    });
+    if let Some(at) = expires_at(paste) {
+        json["expires_at"] = humantime::format_rfc3339_seconds(at).to_string().into();
+    }
+    json
+}
+
+// Counted from now, which is close enough to when the service created the paste
+fn expires_at(paste: &PasteResult) -> Option<std::time::SystemTime> {
+    let seconds = paste.expires?.seconds()?;
+    Some(std::time::SystemTime::now() + std::time::Duration::from_secs(seconds))
+}
+
+fn render_qr(text: &str) -> Result<String, qrcode::types::QrError> {
+    use qrcode::render::unicode::Dense1x2;
+
+    let code = qrcode::QrCode::new(text)?;
+    // Light modules are drawn with the foreground color, which reads correctly on the usual dark
+    // terminal themes; phone scanners also accept the inverted code shown on light themes
+    Ok(code.render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .quiet_zone(true)
+        .build())
+}
+
+// The proxy the client picks up from the environment for `scheme` URLs, looked up like reqwest
+// does: the scheme's own variable wins over ALL_PROXY, and CGI scripts ignore HTTP_PROXY
+fn env_proxy(scheme: &str) -> Option<String> {
+    let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+    let own = match scheme {
+        "http" if std::env::var_os("REQUEST_METHOD").is_some() => None,
+        "http" => var("HTTP_PROXY").or_else(|| var("http_proxy")),
+        _ => var("HTTPS_PROXY").or_else(|| var("https_proxy")),
+    };
+    own.or_else(|| var("ALL_PROXY")).or_else(|| var("all_proxy"))
+}
+
+fn clipboard() -> Result<arboard::Clipboard, String> {
+    // X11 and Wayland clipboards are reached through the display, which SSH sessions and CI lack
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+        return Err("neither DISPLAY nor WAYLAND_DISPLAY is set".to_string());
+    }
+    arboard::Clipboard::new().map_err(|e| e.to_string())
+}
+
+fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    let mut clipboard = clipboard()?;
+    // X11 and Wayland serve the clipboard from the process that set it, so something has to
+    // outlive `paste` for the URL to stay there
+    #[cfg(all(unix, not(target_os = "macos")))]
+    match spawn_clipboard_owner(text).or_else(|e| {
+        tracing::debug!("could not leave a process behind to own the clipboard: {}", e);
+        copy_with_tool(text)
+    }) {
+        Ok(()) => return Ok(()),
+        Err(e) => tracing::warn!("the URL only stays on the clipboard if a clipboard manager takes it over before paste exits: {}", e),
+    }
+    clipboard.set_text(text).map_err(|e| e.to_string())
+}
+
+// Set in the environment of the copy of `paste` that keeps the clipboard after it exits
+#[cfg(all(unix, not(target_os = "macos")))]
+const CLIPBOARD_OWNER: &str = "ZERO_PASTE_CLIPBOARD_OWNER";
+
+// Runs in the child, holding on to the text from stdin until something else is copied
+#[cfg(all(unix, not(target_os = "macos")))]
+fn own_clipboard() -> i32 {
+    use arboard::SetExtLinux;
+
+    let mut text = String::new();
+    if std::io::Read::read_to_string(&mut std::io::stdin(), &mut text).is_err() {
+        return 1;
+    }
+    match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set().wait().text(text)) {
+        Ok(()) => 0,
+        Err(_) => 1,
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn spawn_clipboard_owner(text: &str) -> Result<(), String> {
+    use std::io::Write;
+    use std::os::unix::process::CommandExt;
+
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let mut child = std::process::Command::new(exe)
+        .env(CLIPBOARD_OWNER, "1")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        // Out of the terminal's process group, so a Ctrl-C meant for `paste` doesn't reach it
+        .process_group(0)
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    child.stdin.take().unwrap().write_all(text.as_bytes()).map_err(|e| e.to_string())?;
+
+    // It only exits this soon if it couldn't get at the clipboard
+    for _ in 0..10 {
+        match child.try_wait() {
+            Ok(Some(status)) if !status.success() => return Err(format!("the clipboard owner exited with {}", status)),
+            Ok(Some(_)) => return Ok(()),
+            Ok(None) => std::thread::sleep(std::time::Duration::from_millis(20)),
+            Err(e) => return Err(e.to_string()),
         }
     }
+    Ok(())
+}
+
+// wl-copy and xclip stay in the background by themselves to serve the clipboard
+#[cfg(all(unix, not(target_os = "macos")))]
+fn copy_with_tool(text: &str) -> Result<(), String> {
+    use std::io::Write;
 
-    None
-}
\ No newline at end of file
+    let (tool, args): (&str, &[&str]) = match std::env::var_os("WAYLAND_DISPLAY") {
+        Some(_) => ("wl-copy", &[]),
+        None => ("xclip", &["-selection", "clipboard"]),
+    };
+    let mut child = std::process::Command::new(tool)
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map_err(|e| format!("could not run {}: {}", tool, e))?;
+    child.stdin.take().unwrap().write_all(text.as_bytes()).map_err(|e| e.to_string())?;
+    match child.wait() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("{} exited with {}", tool, status)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn paste_from_clipboard() -> Result<String, String> {
+    let mut clipboard = clipboard().map_err(|e| format!("no clipboard is available: {}", e))?;
+    let text = match clipboard.get_text() {
+        Err(arboard::Error::ContentNotAvailable) => return Err("the clipboard doesn't hold any text".to_string()),
+        result => result.map_err(|e| format!("could not read the clipboard: {}", e))?,
+    };
+    if text.is_empty() {
+        return Err("the clipboard is empty".to_string());
+    }
+    Ok(text)
+}
@@ -0,0 +1,156 @@
+/// Minifies `text` for the lexer `lang`: JSON is compacted, CSS loses its comments and most
+/// whitespace, and JavaScript its indentation, blank lines and comment lines. `Ok(None)` means
+/// there's no minifier for the language, since guessing at one could change what the code does.
+pub fn minify(lang: &str, text: &str) -> Result<Option<String>, String> {
+    let minified = match lang {
+        "json" => minify_json(text)?,
+        "css" => minify_css(text)?,
+        "js" => minify_js(text),
+        _ => return Ok(None),
+    };
+    Ok(Some(minified))
+}
+
+fn minify_json(text: &str) -> Result<String, String> {
+    // Checked first, so the scan below only ever sees valid JSON
+    for value in serde_json::Deserializer::from_str(text).into_iter::<serde::de::IgnoredAny>() {
+        value.map_err(|e| e.to_string())?;
+    }
+
+    // Re-serializing would sort the keys, so whitespace is dropped in place instead
+    let mut minified = String::with_capacity(text.len());
+    let (mut depth, mut in_string, mut escaped) = (0usize, false, false);
+    for c in text.chars() {
+        if in_string {
+            minified.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth -= 1,
+            // Values on lines of their own stay that way, so JSON Lines remain valid
+            '\n' if depth == 0 => {
+                if !minified.is_empty() && !minified.ends_with('\n') {
+                    minified.push('\n');
+                }
+                continue;
+            }
+            _ if c.is_whitespace() => continue,
+            _ => {}
+        }
+        minified.push(c);
+    }
+    Ok(minified)
+}
+
+fn minify_css(text: &str) -> Result<String, String> {
+    // Whitespace around these never matters; before a `:` it does, as in `a :hover`
+    const AROUND: &[char] = &['{', '}', ';', ','];
+    const AFTER: &[char] = &['{', '}', ';', ',', ':'];
+
+    let mut minified = String::with_capacity(text.len());
+    let mut space = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if last == '*' => break,
+                        Some(c) => last = c,
+                        None => return Err("unterminated comment".to_string()),
+                    }
+                }
+                space = true;
+                continue;
+            }
+            _ if c.is_whitespace() => {
+                space = true;
+                continue;
+            }
+            _ => {}
+        }
+
+        if space && !minified.is_empty() && !minified.ends_with(AFTER) && !AROUND.contains(&c) {
+            minified.push(' ');
+        }
+        space = false;
+        minified.push(c);
+        if c == '"' || c == '\'' {
+            let mut escaped = false;
+            loop {
+                let Some(next) = chars.next() else { return Err("unterminated string".to_string()) };
+                minified.push(next);
+                match next {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    _ if next == c => break,
+                    _ => {}
+                }
+            }
+        }
+    }
+    if text.ends_with('\n') {
+        minified.push('\n');
+    }
+    Ok(minified)
+}
+
+// Only whole lines are touched: collapsing lines would trip up automatic semicolon insertion, and
+// `//` may just as well start a regex or sit in a string. Template literals are kept as they are.
+fn minify_js(text: &str) -> String {
+    let mut minified = String::with_capacity(text.len());
+    let mut in_template = false;
+    for line in text.lines() {
+        if in_template {
+            minified.push_str(line);
+            minified.push('\n');
+            in_template = ends_in_template(line, true);
+            continue;
+        }
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("//") {
+            continue;
+        }
+        minified.push_str(line);
+        minified.push('\n');
+        in_template = ends_in_template(line, false);
+    }
+    if !text.ends_with('\n') {
+        minified.pop();
+    }
+    minified
+}
+
+// Whether a template literal is still open at the end of `line`. Quotes are taken to close on the
+// same line, which is all that plain strings can do.
+fn ends_in_template(line: &str, mut in_template: bool) -> bool {
+    let mut quote = None;
+    let mut escaped = false;
+    for c in line.chars() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match (c, quote) {
+            ('\\', _) => escaped = true,
+            ('`', _) if in_template => in_template = false,
+            (_, _) if in_template => {}
+            (_, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {}
+            ('\'' | '"', None) => quote = Some(c),
+            ('`', None) => in_template = true,
+            _ => {}
+        }
+    }
+    in_template
+}
@@ -0,0 +1,46 @@
+use crate::PasteError;
+use reqwest::Url;
+
+/// Whether a file argument names a remote resource rather than a path.
+pub fn is_remote(file: &str) -> bool {
+    file.starts_with("http://") || file.starts_with("https://")
+}
+
+/// Downloads `url` to upload it again. Anything longer than `limit` bytes is refused, and so is a
+/// response that says it's binary unless `binary` is set.
+pub async fn fetch_url(client: &reqwest::Client, url: &Url, limit: Option<u64>, binary: bool) -> Result<Vec<u8>, PasteError> {
+    tracing::debug!("fetching {}", url);
+    let res = client.get(url.clone()).send().await?;
+    tracing::debug!("{} returned {}", res.url(), res.status());
+    if res.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(PasteError::NotFound(url.clone()));
+    }
+    let mut res = res.error_for_status()?;
+
+    let content_type = res.headers().get(reqwest::header::CONTENT_TYPE).and_then(|value| value.to_str().ok()).unwrap_or_default();
+    let essence = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+    if !binary && !essence.is_empty() && !is_text(&essence) {
+        return Err(PasteError::BinaryContent(essence));
+    }
+
+    // Checked as the body arrives, since the length the server announces may be missing or wrong
+    let too_large = || PasteError::RemoteTooLarge { url: url.clone(), limit: limit.unwrap_or_default() };
+    if limit.zip(res.content_length()).is_some_and(|(limit, len)| len > limit) {
+        return Err(too_large());
+    }
+    let mut content = Vec::new();
+    while let Some(chunk) = res.chunk().await? {
+        content.extend_from_slice(&chunk);
+        if limit.is_some_and(|limit| content.len() as u64 > limit) {
+            return Err(too_large());
+        }
+    }
+    Ok(content)
+}
+
+fn is_text(mime: &str) -> bool {
+    mime.starts_with("text/")
+        || mime.ends_with("+json")
+        || mime.ends_with("+xml")
+        || matches!(mime, "application/json" | "application/xml" | "application/javascript" | "application/x-sh" | "application/toml" | "application/yaml" | "application/x-yaml")
+}
@@ -0,0 +1,43 @@
+use crate::PasteError;
+use serde::de::{Deserialize, IgnoredAny};
+
+/// Parses `text` as JSON, YAML or TOML, going by the lexer `lang` and, since TOML is highlighted
+/// as `ini`, the file name. Content in any other language passes unchecked.
+pub fn check_syntax(lang: &str, filename: Option<&str>, text: &str) -> Result<(), PasteError> {
+    let toml = filename.is_some_and(|name| name.to_lowercase().ends_with(".toml"));
+    let (format, result) = match lang {
+        "json" => ("JSON", check_json(text)),
+        "yaml" => ("YAML", check_yaml(text)),
+        "ini" if toml => ("TOML", check_toml(text)),
+        _ => return Ok(()),
+    };
+    result.map_err(|reason| PasteError::InvalidSyntax { format, reason })
+}
+
+// JSON Lines are highlighted as JSON too, so any number of values may follow each other
+fn check_json(text: &str) -> Result<(), String> {
+    for value in serde_json::Deserializer::from_str(text).into_iter::<IgnoredAny>() {
+        value.map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn check_yaml(text: &str) -> Result<(), String> {
+    for document in serde_yaml::Deserializer::from_str(text) {
+        IgnoredAny::deserialize(document).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn check_toml(text: &str) -> Result<(), String> {
+    let e = match text.parse::<toml::Table>() {
+        Ok(_) => return Ok(()),
+        Err(e) => e,
+    };
+    let message = e.message().to_string();
+    let Some(span) = e.span() else { return Err(message) };
+    let before = &text[..span.start];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+    Err(format!("{} at line {} column {}", message, line, column))
+}
@@ -0,0 +1,189 @@
+use crate::PasteError;
+
+/// Converts CRLF and lone CR line endings to LF.
+pub fn normalize_eol(content: &str) -> String {
+    content.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Strips spaces and tabs from the end of every line, keeping the line endings as they are.
+pub fn trim_trailing(content: &str) -> String {
+    content.split_inclusive('\n').map(|line| {
+        let body = line.trim_end_matches(['\n', '\r']);
+        let ending = &line[body.len()..];
+        format!("{}{}", body.trim_end_matches([' ', '\t']), ending)
+    }).collect()
+}
+
+/// Replaces tabs with spaces up to the next multiple of `width` columns, so the indentation looks
+/// the same as in an editor with that tab width.
+pub fn expand_tabs(content: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut expanded = String::with_capacity(content.len());
+    let mut column = 0;
+    for c in content.chars() {
+        match c {
+            '\t' => {
+                let spaces = width - column % width;
+                expanded.extend(std::iter::repeat_n(' ', spaces));
+                column += spaces;
+            }
+            '\n' | '\r' => {
+                expanded.push(c);
+                column = 0;
+            }
+            _ => {
+                expanded.push(c);
+                column += 1;
+            }
+        }
+    }
+    expanded
+}
+
+/// Breaks lines longer than `width` characters, at the last space that fits where there is one
+/// and in the middle of the word otherwise. The space a line is broken at is dropped.
+pub fn wrap_lines(content: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut wrapped = String::with_capacity(content.len());
+    for line in content.split_inclusive('\n') {
+        let body = line.trim_end_matches(['\n', '\r']);
+        let ending = &line[body.len()..];
+        let newline = if ending.is_empty() { "\n" } else { ending };
+
+        let mut rest = body;
+        while let Some((split, next)) = rest.char_indices().nth(width) {
+            // A space right after the last character that fits is as good a break as any before it
+            let window = &rest[..split + next.len_utf8()];
+            match window.rfind([' ', '\t']).filter(|i| *i > 0) {
+                Some(i) => {
+                    wrapped.push_str(&rest[..i]);
+                    rest = &rest[i + 1..];
+                }
+                None => {
+                    wrapped.push_str(&rest[..split]);
+                    rest = &rest[split..];
+                }
+            }
+            wrapped.push_str(newline);
+        }
+        wrapped.push_str(rest);
+        wrapped.push_str(ending);
+    }
+    wrapped
+}
+
+// Longer titles get cut, as services tend to show them in a single line
+const MAX_TITLE_CHARS: usize = 80;
+// Markers in front of headings and comments, which make no sense in a title
+const TITLE_MARKERS: &[&str] = &["<!--", "-->", "/*", "*/", "//", "--", "#", ";", "%", "*"];
+
+/// A title taken from the first line of `content` that has any text, without heading or comment
+/// markers around it and cut at 80 characters. Shebang lines are skipped.
+pub fn first_line_title(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let mut line = line.trim();
+        if line.starts_with("#!") {
+            return None;
+        }
+        while let Some(rest) = TITLE_MARKERS.iter().find_map(|marker| line.strip_prefix(marker)) {
+            line = rest.trim_start();
+        }
+        let line = line.trim_end_matches("*/").trim_end_matches("-->").trim_end();
+        // Closing hashes of a heading, but not those of a word like C#
+        let line = match line.trim_end_matches('#') {
+            rest if rest.is_empty() || rest.ends_with(' ') => rest.trim_end(),
+            _ => line,
+        };
+        let title: String = line.chars().map(|c| if c.is_control() { ' ' } else { c }).take(MAX_TITLE_CHARS).collect();
+        (!title.is_empty()).then(|| title.trim_end().to_string())
+    })
+}
+
+/// Puts each of `before` on a line of its own above `content` and each of `after` below it, in
+/// the order given.
+pub fn add_lines(content: &[u8], before: &[String], after: &[String]) -> Vec<u8> {
+    let extra: usize = before.iter().chain(after).map(|line| line.len() + 1).sum();
+    let mut with_lines = Vec::with_capacity(content.len() + extra + 1);
+    for line in before {
+        with_lines.extend_from_slice(line.as_bytes());
+        with_lines.push(b'\n');
+    }
+    with_lines.extend_from_slice(content);
+    if !after.is_empty() && !content.is_empty() && !content.ends_with(b"\n") {
+        with_lines.push(b'\n');
+    }
+    for line in after {
+        with_lines.extend_from_slice(line.as_bytes());
+        with_lines.push(b'\n');
+    }
+    with_lines
+}
+
+/// Encodes `content` as base64, wrapped at 76 columns like MIME so the paste stays readable.
+pub fn encode_base64(content: &[u8]) -> String {
+    use base64::Engine;
+
+    let encoded = base64::engine::general_purpose::STANDARD.encode(content);
+    let mut wrapped = String::with_capacity(encoded.len() + encoded.len() / 76 + 1);
+    for line in encoded.as_bytes().chunks(76) {
+        // Base64 is ASCII, so every chunk is valid UTF-8
+        wrapped.push_str(std::str::from_utf8(line).unwrap());
+        wrapped.push('\n');
+    }
+    wrapped
+}
+
+/// Decodes base64 from `encode_base64`, or any other base64 with whitespace in it.
+pub fn decode_base64(content: &[u8]) -> Result<Vec<u8>, PasteError> {
+    use base64::Engine;
+
+    let compact: Vec<u8> = content.iter().copied().filter(|b| !b.is_ascii_whitespace()).collect();
+    base64::engine::general_purpose::STANDARD.decode(compact).map_err(|e| PasteError::InvalidBase64(e.to_string()))
+}
+
+/// A 1-based, inclusive range of lines, written `START:END`. Without an `END` it runs to the last line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: Option<usize>,
+}
+
+impl std::str::FromStr for LineRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid line range `{}`, expected START:END such as 40:80", s);
+        let (start, end) = s.split_once(':').ok_or_else(invalid)?;
+        let start: usize = start.trim().parse().map_err(|_| invalid())?;
+        let end = match end.trim() {
+            "" => None,
+            end => Some(end.parse::<usize>().map_err(|_| invalid())?),
+        };
+        if start == 0 || end.is_some_and(|end| end < start) {
+            return Err(invalid());
+        }
+        Ok(LineRange { start, end })
+    }
+}
+
+impl std::fmt::Display for LineRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.end {
+            Some(end) => write!(f, "{}:{}", self.start, end),
+            None => write!(f, "{}:", self.start),
+        }
+    }
+}
+
+/// Cuts `range` out of `content`, keeping the line endings.
+pub fn slice_lines(content: &[u8], range: LineRange) -> Result<&[u8], PasteError> {
+    let lines: Vec<&[u8]> = content.split_inclusive(|b| *b == b'\n').collect();
+    let end = range.end.unwrap_or(lines.len());
+    if range.start > lines.len() || end > lines.len() {
+        return Err(PasteError::LinesOutOfRange { range, lines: lines.len() });
+    }
+
+    let start: usize = lines[..range.start - 1].iter().map(|line| line.len()).sum();
+    let len: usize = lines[range.start - 1..end].iter().map(|line| line.len()).sum();
+    Ok(&content[start..start + len])
+}
@@ -0,0 +1,43 @@
+use std::io::Read;
+
+#[test]
+fn archives_directory_respecting_gitignore_and_depth() {
+    let dir = std::env::temp_dir().join(format!("zero-paste-archive-{}", std::process::id())).join("project");
+    std::fs::create_dir_all(dir.join("src/deep/deeper")).unwrap();
+    std::fs::create_dir_all(dir.join(".git")).unwrap();
+    std::fs::write(dir.join(".gitignore"), "target/\n").unwrap();
+    std::fs::write(dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+    std::fs::write(dir.join("src/deep/deeper/far.rs"), "").unwrap();
+    std::fs::create_dir_all(dir.join("target")).unwrap();
+    std::fs::write(dir.join("target/out"), "build output").unwrap();
+    std::fs::write(dir.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+
+    let names = |archive: Vec<u8>| {
+        let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(&archive[..]));
+        let mut names: Vec<_> = tar.entries().unwrap().map(|entry| entry.unwrap().path().unwrap().display().to_string()).collect();
+        names.sort();
+        names
+    };
+
+    let archive = zero_paste::archive_dir(&dir, 2, true).unwrap();
+    assert_eq!(names(archive), ["project", "project/.gitignore", "project/src", "project/src/deep", "project/src/main.rs"]);
+
+    let archive = zero_paste::archive_dir(&dir, 16, false).unwrap();
+    let names = names(archive);
+    assert!(names.contains(&"project/target/out".to_string()));
+    assert!(names.contains(&"project/src/deep/deeper/far.rs".to_string()));
+    assert!(!names.iter().any(|name| name.contains(".git/")));
+
+    let mut content = String::new();
+    let archive = zero_paste::archive_dir(&dir.join("src"), 1, true).unwrap();
+    let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(&archive[..]));
+    for entry in tar.entries().unwrap() {
+        let mut entry = entry.unwrap();
+        if entry.path().unwrap().ends_with("main.rs") {
+            entry.read_to_string(&mut content).unwrap();
+        }
+    }
+    assert_eq!(content, "fn main() {}\n");
+
+    std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+}
@@ -0,0 +1,189 @@
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod common;
+use common::csrf_form;
+
+async fn haste() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/documents"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"key": "abcde"})))
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn mozilla() -> MockServer {
+    let server = MockServer::start().await;
+    let form = csrf_form("abc123");
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(form))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(302).insert_header("Location", "/Ab3dEf"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/Ab3dEf"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn paste(server: &MockServer, name: &str, args: &[&str]) -> std::process::Output {
+    paste_to(server, "haste", name, args).await
+}
+
+// Uploads a small Rust file to `server` with `args`, with stdin and stdout not on a terminal
+async fn paste_to(server: &MockServer, backend: &str, name: &str, args: &[&str]) -> std::process::Output {
+    // A home of its own keeps the user's config and history out of it
+    let home = std::env::temp_dir().join(format!("zero-paste-cli-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&home).unwrap();
+    let file = home.join("hello.rs");
+    std::fs::write(&file, "fn main() {}\n").unwrap();
+
+    let output = tokio::process::Command::new(env!("CARGO_BIN_EXE_paste"))
+        .args(["-b", backend, "--base-url", &server.uri()])
+        .args(args)
+        .arg(&file)
+        .env("HOME", &home)
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_DATA_HOME", home.join("data"))
+        .env("XDG_CACHE_HOME", home.join("cache"))
+        // Nor should --copy get at the clipboard of whoever runs the tests
+        .env_remove("DISPLAY")
+        .env_remove("WAYLAND_DISPLAY")
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+        .unwrap();
+    std::fs::remove_dir_all(&home).unwrap();
+    output
+}
+
+#[tokio::test]
+async fn stdout_carries_only_the_url() {
+    let server = haste().await;
+    let output = paste(&server, "stdout", &["--qr"]).await;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), format!("{}/abcde\n", server.uri()));
+    assert!(String::from_utf8(output.stderr).unwrap().contains("lang: rust"));
+}
+
+#[tokio::test]
+async fn format_shapes_the_output() {
+    let server = haste().await;
+    let output = paste(&server, "format", &["--format", "[{lang}]({url}) {{{bytes}}}"]).await;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), format!("[rust]({}/abcde) {{13}}\n", server.uri()));
+
+    let output = paste(&server, "format-typo", &["--format", "{link}"]).await;
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr).unwrap().contains("unknown placeholder {link}"));
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn interactive_uses_the_defaults_without_a_terminal() {
+    let server = haste().await;
+    let output = paste(&server, "interactive", &["--interactive"]).await;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), format!("{}/abcde\n", server.uri()));
+}
+
+#[tokio::test]
+async fn json_output_describes_the_uploaded_content() {
+    use sha2::Digest;
+
+    let server = haste().await;
+    let output = paste(&server, "json", &["--json"]).await;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let sha256: String = sha2::Sha256::digest(b"fn main() {}\n").iter().map(|byte| format!("{:02x}", byte)).collect();
+    assert_eq!(report["bytes"], 13);
+    assert_eq!(report["sha256"], sha256);
+    // haste keeps pastes around, whatever expire time was asked for
+    assert_eq!(report["expires"], "never");
+    assert_eq!(report["one_time"], false);
+}
+
+#[tokio::test]
+async fn lang_from_leaves_files_alone() {
+    let server = haste().await;
+    let output = paste(&server, "lang-from", &["--json", "--lang-from", "data.json"]).await;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["lang"], "rust");
+    assert!(String::from_utf8(output.stderr).unwrap().contains("--lang-from only applies to stdin"));
+}
+
+#[tokio::test]
+async fn one_time_pastes_come_with_a_warning() {
+    let (mozilla, haste) = (mozilla().await, haste().await);
+    let stderr = |output: std::process::Output| String::from_utf8(output.stderr).unwrap();
+
+    assert!(stderr(paste_to(&mozilla, "mozilla", "once", &["--expire", "once"]).await).contains("destroyed by the first view"));
+    assert!(!stderr(paste_to(&mozilla, "mozilla", "day", &["--expire", "1d"]).await).contains("destroyed by the first view"));
+    // Only services with one-time pastes make one
+    assert!(!stderr(paste(&haste, "haste-once", &["--expire", "once"]).await).contains("destroyed by the first view"));
+}
+
+// Elsewhere the clipboard doesn't depend on the environment, so there's no taking it away
+#[cfg(all(unix, not(target_os = "macos")))]
+#[tokio::test]
+async fn the_clipboard_is_optional_without_a_display() {
+    let server = haste().await;
+    let output = paste(&server, "copy", &["--copy"]).await;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), format!("{}/abcde\n", server.uri()));
+    assert!(String::from_utf8(output.stderr).unwrap().contains("could not copy to the clipboard: neither DISPLAY nor WAYLAND_DISPLAY is set"));
+}
+
+#[tokio::test]
+async fn huge_piped_input_is_streamed() {
+    use tokio::io::AsyncWriteExt;
+
+    let server = haste().await;
+    let home = std::env::temp_dir().join(format!("zero-paste-cli-piped-{}", std::process::id()));
+    let mut child = tokio::process::Command::new(env!("CARGO_BIN_EXE_paste"))
+        .args(["-b", "haste", "--base-url", &server.uri(), "--yes", "--json", "-"])
+        .env("HOME", &home)
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_DATA_HOME", home.join("data"))
+        .env("XDG_CACHE_HOME", home.join("cache"))
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Past the 8 MiB that are held in memory at most
+    let line = "log line that goes on and on\n".repeat(16);
+    let mut stdin = child.stdin.take().unwrap();
+    let lines = 20 * 1024 * 1024 / line.len();
+    let writer = tokio::spawn(async move {
+        for _ in 0..lines {
+            stdin.write_all(line.as_bytes()).await.unwrap();
+        }
+    });
+    let output = child.wait_with_output().await.unwrap();
+    writer.await.unwrap();
+    let _ = std::fs::remove_dir_all(&home);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let sent = &server.received_requests().await.unwrap()[0];
+    assert_eq!(report["bytes"], lines * 464);
+    assert_eq!(sent.body.len(), lines * 464);
+    assert!(!sent.headers.contains_key("content-length"));
+}
@@ -0,0 +1,82 @@
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zero_paste::{ClientOptions, MozillaPaste, PasteBackend, PasteError, UploadOptions};
+
+mod common;
+use common::csrf_form;
+
+#[test]
+fn accepts_proxy_url() {
+    let opts = ClientOptions { proxy: Some("socks5h://127.0.0.1:1080".to_string()), ..Default::default() };
+    assert!(zero_paste::client(&opts).is_ok());
+}
+
+#[test]
+fn invalid_proxy_url_is_a_clear_error() {
+    for proxy in ["not a url", "ftp://proxy.example.com"] {
+        let opts = ClientOptions { proxy: Some(proxy.to_string()), ..Default::default() };
+        let err = zero_paste::client(&opts).unwrap_err();
+        assert!(matches!(err, PasteError::InvalidProxy { .. }));
+        assert!(err.to_string().starts_with(&format!("invalid proxy URL '{}'", proxy)));
+    }
+}
+
+#[test]
+fn malformed_header_is_a_clear_error() {
+    for header in ["Authorization", "Bad Name: value", "X-Test: line\nbreak"] {
+        let opts = ClientOptions { headers: vec![header.to_string()], ..Default::default() };
+        let err = zero_paste::client(&opts).unwrap_err();
+        assert!(matches!(err, PasteError::InvalidHeader { .. }), "{}", header);
+    }
+}
+
+#[tokio::test]
+async fn extra_headers_are_sent_without_replacing_the_form_type() {
+    let server = MockServer::start().await;
+    let form = csrf_form("abc123");
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .and(header("authorization", "Bearer secret"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(form))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(header("authorization", "Bearer secret"))
+        .and(header("content-type", "application/x-www-form-urlencoded"))
+        .respond_with(ResponseTemplate::new(302).insert_header("Location", "/Ab3dEf"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/Ab3dEf"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let headers = vec!["Authorization: Bearer secret".to_string(), "Content-Type: text/plain".to_string()];
+    let client = zero_paste::client(&ClientOptions { headers, ..Default::default() }).unwrap();
+    let backend = MozillaPaste::with_base_url(client, server.uri().parse().unwrap()).unwrap();
+    let uploaded = backend.upload("hello", &UploadOptions::default()).await.unwrap();
+
+    assert_eq!(uploaded.url.path(), "/Ab3dEf");
+}
+
+#[tokio::test]
+async fn follows_redirects_up_to_the_limit() {
+    let server = MockServer::start().await;
+    for hop in 0..3 {
+        Mock::given(method("GET"))
+            .and(path(format!("/{}", hop)))
+            .respond_with(ResponseTemplate::new(302).insert_header("Location", format!("/{}", hop + 1)))
+            .mount(&server)
+            .await;
+    }
+    Mock::given(method("GET")).and(path("/3")).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+
+    let client = zero_paste::client(&ClientOptions { max_redirects: 3, ..Default::default() }).unwrap();
+    let res = client.get(format!("{}/0", server.uri())).send().await.unwrap();
+    assert_eq!(res.url().path(), "/3");
+
+    let client = zero_paste::client(&ClientOptions { max_redirects: 2, ..Default::default() }).unwrap();
+    let err = client.get(format!("{}/0", server.uri())).send().await.unwrap_err();
+    assert!(err.is_redirect(), "{}", err);
+}
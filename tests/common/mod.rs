@@ -0,0 +1,4 @@
+/// The paste form as paste.mozilla.org serves it, down to the hidden CSRF token.
+pub fn csrf_form(token: &str) -> String {
+    format!(r#"<form method="post"><input type="hidden" name="csrfmiddlewaretoken" value="{}"></form>"#, token)
+}
@@ -0,0 +1,50 @@
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zero_paste::crypto::{decrypt_with_password, encrypt_with_password, is_password_encrypted, Encryption};
+use zero_paste::{FileOptions, HasteBackend, PasteBackend, PasteError};
+
+#[tokio::test]
+async fn password_encryption_round_trips_through_the_service() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/documents"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"key": "secret"})))
+        .mount(&server)
+        .await;
+    let backend = HasteBackend::new(reqwest::Client::new(), server.uri().parse().unwrap()).unwrap();
+
+    let opts = FileOptions {
+        encryption: Some(Encryption::Password("hunter2".to_string())),
+        ..FileOptions::default()
+    };
+    let prepared = zero_paste::prepare_content(&backend, b"fn main() {}\n".to_vec(), Some("main.rs"), &opts).unwrap();
+    assert_eq!(prepared.opts.lang, "_text");
+    let paste = zero_paste::upload_prepared(&backend, &prepared).await.unwrap();
+
+    // The service only ever saw ciphertext, and serves it back as it was stored
+    let uploaded = server.received_requests().await.unwrap()[0].body.clone();
+    assert!(is_password_encrypted(&uploaded));
+    assert!(!String::from_utf8_lossy(&uploaded).contains("fn main"));
+    Mock::given(method("GET"))
+        .and(path("/raw/secret"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(uploaded))
+        .mount(&server)
+        .await;
+
+    let fetched = backend.fetch(&paste.url).await.unwrap();
+    assert_eq!(decrypt_with_password(&fetched, "hunter2").unwrap(), b"fn main() {}\n");
+}
+
+#[test]
+fn wrong_password_is_rejected() {
+    let encrypted = encrypt_with_password(b"content", "right").unwrap();
+    assert!(matches!(decrypt_with_password(&encrypted, "wrong"), Err(PasteError::Decryption(_))));
+    assert!(decrypt_with_password(b"plain text", "right").is_err());
+}
+
+#[test]
+fn encrypting_twice_differs() {
+    // Fresh salt and nonce every time, so equal pastes can't be told apart
+    assert_ne!(encrypt_with_password(b"same", "pw").unwrap(), encrypt_with_password(b"same", "pw").unwrap());
+}
@@ -0,0 +1,204 @@
+use zero_paste::{guess_lang_from_content, lang_from_mime_type, line_comment, map_filename_to_lang, SUPPORTED_LANG};
+
+fn guess(content: &str) -> Option<String> {
+    guess_lang_from_content(content)
+}
+
+#[test]
+fn guesses_rust() {
+    let content = "use std::io;\n\nfn main() {\n    let input = io::stdin();\n}\n";
+    assert_eq!(guess(content), Some("rust".to_string()));
+}
+
+#[test]
+fn guesses_python() {
+    let content = "import os\n\ndef main():\n    print(os.getcwd())\n";
+    assert_eq!(guess(content), Some("python".to_string()));
+
+    let content = "from pathlib import Path\n\nfor p in Path('.').iterdir():\n    print(p)\n";
+    assert_eq!(guess(content), Some("python".to_string()));
+}
+
+#[test]
+fn guesses_php() {
+    assert_eq!(guess("<?php\necho 'hello';\n"), Some("php".to_string()));
+}
+
+#[test]
+fn guesses_json() {
+    assert_eq!(guess("{\"name\": \"zero-paste\", \"tags\": [1, 2]}"), Some("json".to_string()));
+    assert_eq!(guess("  [1, 2, 3]\n"), Some("json".to_string()));
+}
+
+#[test]
+fn stays_quiet_when_unsure() {
+    assert_eq!(guess("just some notes about the build\nnothing to see\n"), None);
+    assert_eq!(guess("{ not: json }"), None);
+    assert_eq!(guess("let x = 1;"), None);
+    assert_eq!(guess("import this"), None);
+}
+
+#[test]
+fn maps_dockerfile_variants() {
+    for name in ["Dockerfile", "dockerfile", "Dockerfile.dev", "Dockerfile.prod.arm64", "api.dockerfile", "API.Dockerfile"] {
+        assert_eq!(map_filename_to_lang(name), Some("dker".to_string()), "{}", name);
+    }
+    assert!(SUPPORTED_LANG.contains(&"dker"));
+}
+
+#[test]
+fn leaves_docker_lookalikes_alone() {
+    assert_eq!(map_filename_to_lang("dockerfile_notes.txt"), Some("_text".to_string()));
+    assert_eq!(map_filename_to_lang("docker-compose.yml"), Some("yaml".to_string()));
+}
+
+#[test]
+fn looks_through_template_wrappers() {
+    assert_eq!(map_filename_to_lang("schema.sql.j2"), Some("sql".to_string()));
+    assert_eq!(map_filename_to_lang("config.h.in"), Some("cpp".to_string()));
+    assert_eq!(map_filename_to_lang("Makefile.in"), Some("make".to_string()));
+    assert_eq!(map_filename_to_lang("values.yaml.tmpl"), Some("yaml".to_string()));
+    // Without a recognizable inner file the wrapper's own mapping still applies
+    assert_eq!(map_filename_to_lang("setup.in"), Some("cmake".to_string()));
+}
+
+#[test]
+fn prefers_compound_extensions() {
+    assert_eq!(map_filename_to_lang("module.php.inc"), Some("php".to_string()));
+    assert_eq!(map_filename_to_lang("component.d.ts"), Some("js".to_string()));
+    assert_eq!(map_filename_to_lang("header.inc"), Some("cpp".to_string()));
+    assert_eq!(map_filename_to_lang("archive.tar.gz"), None);
+}
+
+#[test]
+fn maps_the_file_name_of_windows_paths() {
+    assert_eq!(map_filename_to_lang("C:\\src\\main.rs"), Some("rust".to_string()));
+    assert_eq!(map_filename_to_lang("C:\\my.project\\Makefile"), Some("make".to_string()));
+    assert_eq!(map_filename_to_lang("D:/work\\Dockerfile.dev"), Some("dker".to_string()));
+}
+
+#[test]
+fn falls_back_to_mime_types() {
+    assert_eq!(map_filename_to_lang("module.mjs"), Some("js".to_string()));
+    assert_eq!(map_filename_to_lang("data.jsonld"), Some("json".to_string()));
+    assert_eq!(map_filename_to_lang("feed.rss"), Some("xml".to_string()));
+    assert_eq!(map_filename_to_lang("page.xhtml"), Some("html".to_string()));
+    assert_eq!(map_filename_to_lang("README.markdown"), Some("_markdown".to_string()));
+    // Plain text is too vague to pick a lexer, so the content gets a say
+    assert_eq!(map_filename_to_lang("service.conf"), None);
+}
+
+#[test]
+fn maps_mime_types() {
+    assert_eq!(lang_from_mime_type("application/json"), Some("json".to_string()));
+    assert_eq!(lang_from_mime_type("text/x-python"), Some("python".to_string()));
+    assert_eq!(lang_from_mime_type("application/vnd.api+json"), Some("json".to_string()));
+    assert_eq!(lang_from_mime_type("image/svg+xml"), Some("xml".to_string()));
+    assert_eq!(lang_from_mime_type("text/plain"), None);
+    assert_eq!(lang_from_mime_type("application/octet-stream"), None);
+}
+
+#[test]
+fn formats_line_comments() {
+    assert_eq!(line_comment("rust", "main.rs"), Some("// main.rs\n".to_string()));
+    assert_eq!(line_comment("python", "app.py"), Some("# app.py\n".to_string()));
+    assert_eq!(line_comment("html", "index.html"), Some("<!-- index.html -->\n".to_string()));
+    assert_eq!(line_comment("json", "package.json"), None);
+}
+
+#[cfg(feature = "magic")]
+#[test]
+fn recognizes_magic_bytes() {
+    use zero_paste::{detect_lang, magic_mime_type};
+
+    assert_eq!(magic_mime_type(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some("image/png"));
+    assert_eq!(detect_lang(None, b"<?xml version=\"1.0\"?>\n<config/>\n"), Some("xml".to_string()));
+    assert_eq!(detect_lang(None, b"<!DOCTYPE html>\n<title>hi</title>\n"), Some("html".to_string()));
+}
+
+#[test]
+fn maps_every_known_extension() {
+    let table = [
+        ("_text", &["txt"][..]),
+        ("_markdown", &["md"]),
+        ("_rst", &["rst"]),
+                ("_code", &["zig", "nim", "ps1", "r", "jl", "fs", "ml", "proto", "graphql", "tf"]),
+        ("bash", &["sh"]),
+        ("bat", &["bat"]),
+        ("c", &["c"]),
+        ("common-lisp", &["lisp", "lsp", "cl"]),
+        ("cpp", &["cpp", "cc", "cxx", "hpp", "hxx", "inc", "hh", "h"]),
+        ("csharp", &["cs"]),
+        ("cmake", &["cmake", "in"]),
+        ("css", &["css"]),
+        ("dart", &["dart"]),
+        ("diff", &["patch", "diff"]),
+        ("dker", &["dockerfile"]),
+        ("elixir", &["elixir", "ex", "exs"]),
+        ("erlang", &["erl"]),
+        ("go", &["go"]),
+        ("handlebars", &["hbs"]),
+        ("haskell", &["hs"]),
+        ("html", &["html", "htm", "shtm", "shtml", "vue", "svelte", "astro"]),
+        ("ini", &["ini", "toml"]),
+        ("java", &["java", "groovy", "scala"]),
+        ("js", &["js", "ts"]),
+        ("json", &["json", "jsonl"]),
+        ("jsx", &["tsx", "jsx"]),
+        ("kotlin", &["kt", "kts"]),
+        ("lua", &["lua"]),
+        ("objective-c", &["m", "mm"]),
+        ("perl", &["pl"]),
+        ("php", &["php"]),
+        ("python", &["py"]),
+        ("rb", &["rb"]),
+        ("rust", &["rs"]),
+        ("sass", &["sass"]),
+        ("scss", &["scss"]),
+        ("sol", &["sol"]),
+        ("sql", &["sql"]),
+        ("swift", &["swift"]),
+        ("tex", &["tex"]),
+        ("typoscript", &["typoscript"]),
+        ("vim", &["vim"]),
+        ("xml", &["xml"]),
+        ("xslt", &["xsl", "xslt"]),
+        ("yaml", &["yml", "yaml"]),
+    ];
+    for (lang, extensions) in table {
+        assert!(SUPPORTED_LANG.contains(&lang), "{}", lang);
+        for ext in extensions {
+            let name = format!("file.{}", ext);
+            assert_eq!(map_filename_to_lang(&name).as_deref(), Some(lang), "{}", name);
+            let upper = name.to_uppercase();
+            assert_eq!(map_filename_to_lang(&upper).as_deref(), Some(lang), "{}", upper);
+        }
+    }
+}
+
+#[test]
+fn maps_special_file_names() {
+    let cases = [
+        ("Makefile", "make"),
+        ("makefile", "make"),
+        ("MAKEFILE", "make"),
+        ("CMakeLists.txt", "cmake"),
+        ("cmakelists.txt", "cmake"),
+        ("nginx.conf", "nginx"),
+        ("my.nginx.backup", "nginx"),
+        ("NGINX-site", "nginx"),
+        ("Dockerfile", "dker"),
+        ("src/main.rs", "rust"),
+        ("archive.2024.json", "json"),
+    ];
+    for (name, lang) in cases {
+        assert_eq!(map_filename_to_lang(name).as_deref(), Some(lang), "{}", name);
+    }
+}
+
+#[test]
+fn unknown_names_have_no_lexer() {
+    for name in ["foo.unknownext", "README", "noext", ".bashrc", "archive.tar.gz", "photo.png", "trailing.", ""] {
+        assert_eq!(map_filename_to_lang(name), None, "{}", name);
+    }
+}
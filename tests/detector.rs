@@ -0,0 +1,32 @@
+#![cfg(unix)]
+
+use zero_paste::run_detector;
+
+// The file name is added to the command as its last argument
+#[test]
+fn uses_a_supported_answer() {
+    let command = r#"detect() { case "$1" in *.weird) echo rust ;; esac; }; detect"#;
+    assert_eq!(run_detector(command, Some("build.weird"), b""), Some("rust".to_string()));
+    assert_eq!(run_detector(command, Some("build.other"), b""), None);
+}
+
+#[test]
+fn gets_the_file_name_and_content() {
+    let command = r#"detect() { read -r line; [ "$1" = "my file.tpl" ] && [ "$line" = "hello" ] && echo yaml; }; detect"#;
+    assert_eq!(run_detector(command, Some("my file.tpl"), b"hello\nworld\n"), Some("yaml".to_string()));
+    assert_eq!(run_detector(r#"detect() { [ $# -eq 0 ] && echo json; }; detect"#, None, b"{}"), Some("json".to_string()));
+}
+
+#[test]
+fn falls_through_on_bad_answers() {
+    assert_eq!(run_detector("echo not-a-lexer", None, b""), None);
+    assert_eq!(run_detector("true", None, b""), None);
+    assert_eq!(run_detector("detect() { echo rust; exit 1; }; detect", None, b""), None);
+}
+
+#[test]
+fn gives_up_on_a_hanging_detector() {
+    let started = std::time::Instant::now();
+    assert_eq!(run_detector("sleep 10; echo rust", None, b""), None);
+    assert!(started.elapsed() < zero_paste::DETECTOR_TIMEOUT + std::time::Duration::from_secs(2));
+}
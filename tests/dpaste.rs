@@ -0,0 +1,64 @@
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zero_paste::{DpasteBackend, ExpireSpec, PasteBackend, PasteError, UploadOptions};
+
+fn backend(server: &MockServer) -> DpasteBackend {
+    DpasteBackend::with_base_url(reqwest::Client::new(), server.uri().parse().unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn upload_translates_the_lexer_and_reads_the_location() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v2/"))
+        .and(body_string_contains("syntax=javascript"))
+        .and(body_string_contains("expiry_days=3"))
+        .respond_with(ResponseTemplate::new(201).insert_header("Location", format!("{}/ABCD1234", server.uri())))
+        .mount(&server)
+        .await;
+
+    let opts = UploadOptions { expire: ExpireSpec::Seconds(3 * 86400), lang: "js".to_string(), ..Default::default() };
+    let uploaded = backend(&server).upload("let a = 1;", &opts).await.unwrap();
+
+    assert_eq!(uploaded.url.as_str(), format!("{}/ABCD1234", server.uri()));
+    assert_eq!(uploaded.expires, Some(ExpireSpec::Seconds(3 * 86400)));
+}
+
+#[test]
+fn expiry_is_rounded_up_to_whole_days() {
+    let backend = DpasteBackend::new(reqwest::Client::new());
+    let days = |expire| {
+        let opts = UploadOptions { expire, lang: "rust".to_string(), ..Default::default() };
+        backend.fields(&opts).into_iter().find(|(name, _)| *name == "expiry_days").unwrap().1
+    };
+
+    assert_eq!(days(ExpireSpec::Seconds(3600)), "1");
+    assert_eq!(days(ExpireSpec::Seconds(36 * 3600)), "2");
+    assert_eq!(days(ExpireSpec::Seconds(1000 * 86400)), "365");
+    assert_eq!(days(ExpireSpec::Once), "1");
+}
+
+#[tokio::test]
+async fn fetch_reads_the_txt_version() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/ABCD1234.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("hello"))
+        .mount(&server)
+        .await;
+
+    let url = format!("{}/ABCD1234", server.uri()).parse().unwrap();
+    assert_eq!(backend(&server).fetch(&url).await.unwrap(), b"hello");
+}
+
+#[tokio::test]
+async fn upload_reports_the_reason_for_a_rejection() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(400).set_body_string("Invalid syntax choice."))
+        .mount(&server)
+        .await;
+
+    let err = backend(&server).upload("hello", &UploadOptions::default()).await.unwrap_err();
+    assert!(matches!(err, PasteError::UploadRejected { ref reason, .. } if reason == "Invalid syntax choice."));
+}
@@ -0,0 +1,49 @@
+use serde_json::json;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zero_paste::{Fallback, HasteBackend, NullPointerBackend, PasteBackend, PasteError, UploadOptions};
+
+async fn haste(status: u16) -> (MockServer, Box<dyn PasteBackend>) {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(status).set_body_json(json!({"key": "abcde"})))
+        .mount(&server)
+        .await;
+    let backend = HasteBackend::new(reqwest::Client::new(), server.uri().parse().unwrap()).unwrap();
+    (server, Box::new(backend))
+}
+
+#[tokio::test]
+async fn falls_back_to_the_next_backend() {
+    let (_down, primary) = haste(503).await;
+    let (up, secondary) = haste(200).await;
+    let fallback = Fallback::new(vec![("primary".to_string(), primary), ("secondary".to_string(), secondary)]);
+
+    let uploaded = fallback.upload("hello", &UploadOptions::default()).await.unwrap();
+    assert_eq!(uploaded.url.as_str(), format!("{}/abcde", up.uri()));
+    assert_eq!(uploaded.backend.as_deref(), Some("secondary"));
+}
+
+#[tokio::test]
+async fn reports_the_first_error_when_all_fail() {
+    let (_first, primary) = haste(400).await;
+    let (_second, secondary) = haste(503).await;
+    let fallback = Fallback::new(vec![("primary".to_string(), primary), ("secondary".to_string(), secondary)]);
+
+    let err = fallback.upload("hello", &UploadOptions::default()).await.unwrap_err();
+    assert!(matches!(&err, PasteError::Http(e) if e.status() == Some(reqwest::StatusCode::BAD_REQUEST)), "{}", err);
+}
+
+#[tokio::test]
+async fn binary_content_skips_text_backends() {
+    let binary: Box<dyn PasteBackend> = Box::new(NullPointerBackend::new(reqwest::Client::new()));
+    let mixed = Fallback::new(vec![("haste".to_string(), haste(200).await.1), ("0x0".to_string(), binary)]);
+    assert!(mixed.supports_binary());
+    assert!(!mixed.supports_streaming());
+
+    let (text, backend) = haste(200).await;
+    let only_text = Fallback::new(vec![("haste".to_string(), backend)]);
+    let err = only_text.upload_bytes(&[0xff, 0xfe], &UploadOptions::default()).await.unwrap_err();
+    assert!(matches!(err, PasteError::NotUtf8));
+    assert!(text.received_requests().await.unwrap().is_empty());
+}
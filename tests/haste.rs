@@ -0,0 +1,87 @@
+use serde_json::json;
+use wiremock::matchers::{body_string, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zero_paste::{HasteBackend, PasteBackend, PasteError, Retry, UploadOptions};
+
+#[tokio::test]
+async fn upload_builds_url_from_returned_key() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/documents"))
+        .and(body_string("hello"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"key": "abcde"})))
+        .mount(&server)
+        .await;
+
+    let backend = HasteBackend::new(reqwest::Client::new(), server.uri().parse().unwrap()).unwrap();
+    let uploaded = backend.upload("hello", &UploadOptions::default()).await.unwrap();
+
+    assert_eq!(uploaded.url.as_str(), format!("{}/abcde", server.uri()));
+    // The one-time default doesn't apply, haste-server decides how long to keep it
+    assert_eq!(uploaded.expires, None);
+}
+
+#[tokio::test]
+async fn fetch_reads_the_raw_document() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/raw/abcde"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("hello"))
+        .mount(&server)
+        .await;
+
+    let backend = HasteBackend::new(reqwest::Client::new(), server.uri().parse().unwrap()).unwrap();
+    let url = format!("{}/abcde.rs", server.uri()).parse().unwrap();
+
+    assert_eq!(backend.fetch(&url).await.unwrap(), b"hello");
+}
+
+#[tokio::test]
+async fn fetch_reports_missing_pastes() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+
+    let backend = HasteBackend::new(reqwest::Client::new(), server.uri().parse().unwrap()).unwrap();
+    let url = format!("{}/gone", server.uri()).parse().unwrap();
+
+    assert!(matches!(backend.fetch(&url).await, Err(PasteError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn rate_limited_uploads_wait_as_asked() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"key": "abcde"})))
+        .mount(&server)
+        .await;
+
+    let haste = HasteBackend::new(reqwest::Client::new(), server.uri().parse().unwrap()).unwrap();
+    let backend = Retry::new(Box::new(haste), 2);
+    let started = std::time::Instant::now();
+    backend.upload("hello", &UploadOptions::default()).await.unwrap();
+
+    assert!(started.elapsed() >= std::time::Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn long_rate_limits_are_not_waited_out() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "3600"))
+        .mount(&server)
+        .await;
+
+    let haste = HasteBackend::new(reqwest::Client::new(), server.uri().parse().unwrap()).unwrap();
+    let result = Retry::new(Box::new(haste), 2).upload("hello", &UploadOptions::default()).await;
+
+    assert!(matches!(result, Err(PasteError::RateLimited { retry_after: Some(after) }) if after.as_secs() == 3600));
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+}
@@ -0,0 +1,28 @@
+use zero_paste::{ExpireSpec, HistoryEntry};
+
+fn entry(backend: &str, expires: &str, requested_expire: &str) -> HistoryEntry {
+    HistoryEntry {
+        timestamp: humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string(),
+        file: Some("main.rs".to_string()),
+        lang: "rust".to_string(),
+        expires: expires.to_string(),
+        requested_expire: Some(requested_expire.to_string()),
+        url: format!("https://{}.example/abcde", backend),
+        backend: Some(backend.to_string()),
+        delete_token: None,
+        sha256: Some("0123abcd".to_string()),
+        bytes: Some(13),
+    }
+}
+
+#[test]
+fn duplicates_match_the_expire_time_asked_for() {
+    let half_hour = ExpireSpec::Seconds(1800);
+    // paste.mozilla.org rounds 30m to an hour, and gists never expire
+    let history = [entry("mozilla", "1h", "30m"), entry("gist", "never", "30m"), entry("privatebin", "once", "once")];
+
+    assert_eq!(HistoryEntry::find_duplicate(&history, "0123abcd", "mozilla", half_hour).map(|entry| entry.expires.as_str()), Some("1h"));
+    assert_eq!(HistoryEntry::find_duplicate(&history, "0123abcd", "gist", half_hour).map(|entry| entry.expires.as_str()), Some("never"));
+    assert!(HistoryEntry::find_duplicate(&history, "0123abcd", "mozilla", ExpireSpec::Seconds(3600)).is_none());
+    assert!(HistoryEntry::find_duplicate(&history, "0123abcd", "privatebin", ExpireSpec::Once).is_none());
+}
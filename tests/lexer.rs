@@ -0,0 +1,48 @@
+use zero_paste::{DpasteBackend, HasteBackend, MozillaPaste, PasteBackend, Retry, SprungeBackend, UploadOptions, SUPPORTED_LANG};
+
+fn translate(backend: &dyn PasteBackend, lexer: &str) -> Option<String> {
+    backend.translate_lexer(lexer)
+}
+
+#[test]
+fn mozilla_keeps_every_lexer() {
+    let backend = MozillaPaste::new(reqwest::Client::new());
+    for lexer in SUPPORTED_LANG {
+        assert_eq!(translate(&backend, lexer).as_deref(), Some(lexer));
+    }
+}
+
+#[test]
+fn pygments_services_rename_some_lexers() {
+    let backends: [Box<dyn PasteBackend>; 2] = [Box::new(DpasteBackend::new(reqwest::Client::new())), Box::new(SprungeBackend::new(reqwest::Client::new()))];
+    for backend in &backends {
+        assert_eq!(translate(backend.as_ref(), "rb").as_deref(), Some("ruby"));
+        assert_eq!(translate(backend.as_ref(), "js").as_deref(), Some("javascript"));
+        assert_eq!(translate(backend.as_ref(), "rust").as_deref(), Some("rust"));
+        assert_eq!(translate(backend.as_ref(), "_text"), None);
+        assert_eq!(translate(backend.as_ref(), "ipythonconsole"), None);
+    }
+}
+
+#[test]
+fn dpaste_falls_back_to_plain_text() {
+    let backend = DpasteBackend::new(reqwest::Client::new());
+    let syntax = |lang: &str| {
+        let opts = UploadOptions { lang: lang.to_string(), ..Default::default() };
+        backend.fields(&opts).into_iter().find(|(name, _)| *name == "syntax").unwrap().1
+    };
+
+    assert_eq!(syntax("dker"), "docker");
+    assert_eq!(syntax("_code"), "text");
+    assert_eq!(syntax("ipythonconsole"), "text");
+}
+
+#[test]
+fn services_without_lexers_translate_nothing() {
+    let haste = HasteBackend::new(reqwest::Client::new(), "https://hastebin.com".parse().unwrap()).unwrap();
+    assert_eq!(translate(&haste, "rust"), None);
+
+    // Wrappers answer for the backend they wrap
+    let retry = Retry::new(Box::new(DpasteBackend::new(reqwest::Client::new())), 2);
+    assert_eq!(translate(&retry, "rb").as_deref(), Some("ruby"));
+}
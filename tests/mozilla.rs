@@ -0,0 +1,233 @@
+use wiremock::matchers::{body_string_contains, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zero_paste::backend::extract_csrf_token;
+use zero_paste::{Expire, ExpireSpec, MozillaPaste, PasteBackend, PasteError, UploadOptions};
+
+mod common;
+use common::csrf_form;
+
+#[test]
+fn extracts_csrf_token() {
+    let html = csrf_form("abc123");
+    assert_eq!(extract_csrf_token(&html).unwrap(), "abc123");
+}
+
+#[test]
+fn missing_csrf_token_is_an_error() {
+    let html = r#"<form method="post"><textarea name="content"></textarea></form>"#;
+    assert!(matches!(extract_csrf_token(html), Err(PasteError::CsrfTokenMissing)));
+}
+
+#[tokio::test]
+async fn rejected_uploads_are_errors() {
+    let server = MockServer::start().await;
+    let form = csrf_form("abc123");
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(form))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(400).set_body_string(r#"<ul class="errorlist"><li>Select a valid choice.</li></ul>"#))
+        .mount(&server)
+        .await;
+
+    let backend = MozillaPaste::with_base_url(reqwest::Client::new(), server.uri().parse().unwrap()).unwrap();
+    let err = backend.upload("hello", &UploadOptions::default()).await.unwrap_err();
+
+    let PasteError::UploadRejected { status, reason } = err else { panic!("unexpected error: {}", err) };
+    assert_eq!(status, 400);
+    assert_eq!(reason, "Select a valid choice.");
+}
+
+#[tokio::test]
+async fn stale_cached_session_is_refetched() {
+    let server = MockServer::start().await;
+    let cache = std::env::temp_dir().join(format!("zero-paste-session-{}.json", std::process::id()));
+    let session = serde_json::json!({ "base_url": format!("{}/", server.uri()), "cookie": "csrftoken=old", "token": "stale" });
+    std::fs::write(&cache, session.to_string()).unwrap();
+
+    let form = csrf_form("fresh");
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(form))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("csrfmiddlewaretoken=stale"))
+        .respond_with(ResponseTemplate::new(403))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("csrfmiddlewaretoken=fresh"))
+        .respond_with(ResponseTemplate::new(302).insert_header("Location", "/Ab3dEf"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/Ab3dEf"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let backend = MozillaPaste::with_base_url(reqwest::Client::new(), server.uri().parse().unwrap())
+        .unwrap()
+        .with_session_cache(cache.clone());
+    let uploaded = backend.upload("hello", &UploadOptions::default()).await.unwrap();
+
+    assert_eq!(uploaded.url.path(), "/Ab3dEf");
+    let cached = std::fs::read_to_string(&cache).unwrap();
+    std::fs::remove_file(&cache).unwrap();
+    assert!(cached.contains("\"token\":\"fresh\""), "{}", cached);
+}
+
+#[tokio::test]
+async fn expired_csrf_token_is_refetched_once() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(csrf_form("expired")))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(csrf_form("renewed")))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(403))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("csrfmiddlewaretoken=renewed"))
+        .respond_with(ResponseTemplate::new(302).insert_header("Location", "/Ab3dEf"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/Ab3dEf"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let backend = MozillaPaste::with_base_url(reqwest::Client::new(), server.uri().parse().unwrap()).unwrap();
+    let uploaded = backend.upload("hello", &UploadOptions::default()).await.unwrap();
+
+    assert_eq!(uploaded.url.path(), "/Ab3dEf");
+}
+
+#[tokio::test]
+async fn repeated_forbidden_is_an_error() {
+    let server = MockServer::start().await;
+    let form = csrf_form("abc123");
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(form))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(403))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let backend = MozillaPaste::with_base_url(reqwest::Client::new(), server.uri().parse().unwrap()).unwrap();
+    let err = backend.upload("hello", &UploadOptions::default()).await.unwrap_err();
+
+    assert!(matches!(err, PasteError::UploadRejected { status, .. } if status == 403), "{}", err);
+}
+
+#[tokio::test]
+async fn upload_submits_the_form_and_returns_the_redirect() {
+    let server = MockServer::start().await;
+    let base_url = format!("{}/", server.uri());
+    let form = csrf_form("abc123");
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(form))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .and(header("content-type", "application/x-www-form-urlencoded"))
+        .and(header("referer", base_url.as_str()))
+        .and(header("origin", base_url.as_str()))
+        .and(body_string_contains("csrfmiddlewaretoken=abc123"))
+        .and(body_string_contains("content=fn+main%28%29+%7B%7D"))
+        .and(body_string_contains("expires=86400"))
+        .and(body_string_contains("lexer=rust"))
+        .and(body_string_contains("title=main.rs"))
+        .respond_with(ResponseTemplate::new(302).insert_header("Location", "/Ab3dEf"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/Ab3dEf"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let backend = MozillaPaste::with_base_url(reqwest::Client::new(), server.uri().parse().unwrap()).unwrap();
+    // Rounded to the nearest preset the form offers
+    let opts = UploadOptions { expire: ExpireSpec::Seconds(20 * 3600), explicit_expire: true, lang: "rust".to_string(), title: "main.rs".to_string(), filename: None };
+    let uploaded = backend.upload("fn main() {}", &opts).await.unwrap();
+
+    assert_eq!(uploaded.url.as_str(), format!("{}Ab3dEf", base_url));
+    assert_eq!(uploaded.delete_token, None);
+    assert_eq!(uploaded.expires, Some(ExpireSpec::from(Expire::Day)));
+}
+
+#[tokio::test]
+async fn form_page_without_token_is_an_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<html><body>Maintenance</body></html>"))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let backend = MozillaPaste::with_base_url(reqwest::Client::new(), server.uri().parse().unwrap()).unwrap();
+    let err = backend.upload("hello", &UploadOptions::default()).await.unwrap_err();
+
+    assert!(matches!(err, PasteError::CsrfTokenMissing), "{}", err);
+}
+
+#[tokio::test]
+async fn server_errors_are_rejections() {
+    let server = MockServer::start().await;
+    let form = csrf_form("abc123");
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(form))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("<h1>Server Error (500)</h1>"))
+        .mount(&server)
+        .await;
+
+    let backend = MozillaPaste::with_base_url(reqwest::Client::new(), server.uri().parse().unwrap()).unwrap();
+    let err = backend.upload("hello", &UploadOptions::default()).await.unwrap_err();
+
+    assert!(matches!(err, PasteError::UploadRejected { status, .. } if status == 500), "{}", err);
+}
+
+#[tokio::test]
+async fn fetches_token_without_uploading() {
+    let server = MockServer::start().await;
+    let form = csrf_form("abc123");
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(form))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST")).respond_with(ResponseTemplate::new(500)).expect(0).mount(&server).await;
+
+    let backend = MozillaPaste::with_base_url(reqwest::Client::new(), server.uri().parse().unwrap()).unwrap();
+    assert_eq!(backend.fetch_token().await.unwrap(), "abc123");
+}
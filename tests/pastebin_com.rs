@@ -0,0 +1,97 @@
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zero_paste::{ExpireSpec, PasteBackend, PasteError, PastebinComBackend, UploadOptions, Visibility};
+
+fn backend(server: &MockServer, visibility: Visibility) -> PastebinComBackend {
+    PastebinComBackend::new(reqwest::Client::new(), "devkey".to_string(), visibility).with_base_url(server.uri().parse().unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn upload_sends_the_api_fields_and_reads_the_url() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/api_post.php"))
+        .and(body_string_contains("api_dev_key=devkey"))
+        .and(body_string_contains("api_option=paste"))
+        .and(body_string_contains("api_paste_code=hello"))
+        .and(body_string_contains("api_paste_format=javascript"))
+        .and(body_string_contains("api_paste_expire_date=1D"))
+        .and(body_string_contains("api_paste_private=0"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("https://pastebin.com/AbCd1234"))
+        .mount(&server)
+        .await;
+
+    let opts = UploadOptions { expire: ExpireSpec::Seconds(86400), lang: "js".to_string(), ..Default::default() };
+    let uploaded = backend(&server, Visibility::Public).upload("hello", &opts).await.unwrap();
+
+    assert_eq!(uploaded.url.as_str(), "https://pastebin.com/AbCd1234");
+}
+
+#[test]
+fn expiry_goes_to_the_nearest_code() {
+    let backend = PastebinComBackend::new(reqwest::Client::new(), String::new(), Visibility::Unlisted);
+    let field = |expire, name| {
+        let opts = UploadOptions { expire, lang: "_text".to_string(), ..Default::default() };
+        backend.fields(&opts).into_iter().find(|(field, _)| *field == name).map(|(_, value)| value)
+    };
+
+    assert_eq!(field(ExpireSpec::Seconds(3600), "api_paste_expire_date").as_deref(), Some("1H"));
+    assert_eq!(field(ExpireSpec::Seconds(13 * 86400), "api_paste_expire_date").as_deref(), Some("2W"));
+    assert_eq!(field(ExpireSpec::Once, "api_paste_expire_date").as_deref(), Some("10M"));
+    assert_eq!(field(ExpireSpec::Once, "api_paste_private").as_deref(), Some("1"));
+    assert_eq!(field(ExpireSpec::Once, "api_paste_format"), None);
+}
+
+#[tokio::test]
+async fn api_errors_are_rejections_with_the_reason() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("Bad API request, maximum paste file size exceeded"))
+        .mount(&server)
+        .await;
+
+    let err = backend(&server, Visibility::Unlisted).upload("hello", &UploadOptions::default()).await.unwrap_err();
+    assert!(matches!(err, PasteError::UploadRejected { ref reason, .. } if reason == "maximum paste file size exceeded"));
+}
+
+#[tokio::test]
+async fn unknown_formats_fall_back_to_plain_text() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("api_paste_format"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("Bad API request, invalid api_paste_format"))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("https://pastebin.com/AbCd1234"))
+        .mount(&server)
+        .await;
+
+    let opts = UploadOptions { lang: "rust".to_string(), ..Default::default() };
+    let uploaded = backend(&server, Visibility::Unlisted).upload("fn main() {}", &opts).await.unwrap();
+
+    assert_eq!(uploaded.url.as_str(), "https://pastebin.com/AbCd1234");
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn private_pastes_need_a_user_key() {
+    let server = MockServer::start().await;
+    let err = backend(&server, Visibility::Private).upload("hello", &UploadOptions::default()).await.unwrap_err();
+
+    assert!(matches!(err, PasteError::MissingCredential { env: "PASTEBIN_USER_KEY", .. }));
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn fetch_reads_the_raw_paste() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/raw/AbCd1234"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("hello"))
+        .mount(&server)
+        .await;
+
+    let url = format!("{}/AbCd1234", server.uri()).parse().unwrap();
+    assert_eq!(backend(&server, Visibility::Unlisted).fetch(&url).await.unwrap(), b"hello");
+}
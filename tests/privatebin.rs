@@ -0,0 +1,73 @@
+use serde_json::json;
+use wiremock::matchers::{header, method, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zero_paste::{ExpireSpec, PasteBackend, PasteError, PrivateBinBackend, UploadOptions};
+
+#[tokio::test]
+async fn uploads_only_ciphertext_and_keeps_the_key_in_the_fragment() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(header("X-Requested-With", "JSONHttpRequest"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"status": 0, "id": "0123456789abcdef", "deletetoken": "secret"})))
+        .mount(&server)
+        .await;
+
+    let backend = PrivateBinBackend::new(reqwest::Client::new(), server.uri().parse().unwrap()).unwrap();
+    let opts = UploadOptions { expire: ExpireSpec::Seconds(86400), lang: "rust".to_string(), ..Default::default() };
+    let uploaded = backend.upload("fn main() {}\n", &opts).await.unwrap();
+
+    assert_eq!(uploaded.url.query(), Some("0123456789abcdef"));
+    assert!(uploaded.url.fragment().is_some_and(|key| key.len() >= 43));
+    assert_eq!(uploaded.delete_token.as_deref(), Some("secret"));
+
+    let requests = server.received_requests().await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(body["v"], 2);
+    assert_eq!(body["meta"]["expire"], "1day");
+    assert_eq!(body["adata"][1], "syntaxhighlighting");
+    assert!(!String::from_utf8_lossy(&requests[0].body).contains("fn main"));
+
+    // What the server stored decrypts with the key from the URL
+    Mock::given(method("GET"))
+        .and(query_param("pasteid", "0123456789abcdef"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"status": 0, "adata": body["adata"], "ct": body["ct"]})))
+        .mount(&server)
+        .await;
+    assert_eq!(backend.fetch(&uploaded.url).await.unwrap(), b"fn main() {}\n");
+}
+
+#[tokio::test]
+async fn fetch_decrypts_pastes_from_other_clients() {
+    // Encrypted independently, the way PrivateBin's own JavaScript does it
+    let adata = json!([["Q3KZpjX/Hmiyi8Aq2AczZw==", "mapUwL73TMM=", 100000, 256, 128, "aes", "gcm", "zlib"], "plaintext", 0, 0]);
+    let ct = "BqscedyH0cU6KXIKBwAYi+tUTQyf278M8ofRMEHQTpp0ENwgOPpp154XXVm1L/lCL1deGxIG";
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(query_param("pasteid", "f468f5e1b9b2e0f0"))
+        .and(header("X-Requested-With", "JSONHttpRequest"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"status": 0, "v": 2, "adata": adata, "ct": ct})))
+        .mount(&server)
+        .await;
+
+    let backend = PrivateBinBackend::new(reqwest::Client::new(), server.uri().parse().unwrap()).unwrap();
+    let url = format!("{}/?f468f5e1b9b2e0f0#4wBqpZM9xaSheZzJSMawUKKwhdpChKbZ5eu5ky4Vigw", server.uri()).parse().unwrap();
+    assert_eq!(backend.fetch(&url).await.unwrap(), b"hello from PrivateBin\n");
+
+    // A key that's off by one character doesn't decrypt it
+    let url = format!("{}/?f468f5e1b9b2e0f0#4wBqpZM9xaSheZzJSMawUKKwhdpChKbZ5eu5ky4Vigx", server.uri()).parse().unwrap();
+    assert!(matches!(backend.fetch(&url).await, Err(PasteError::Decryption(_))));
+}
+
+#[tokio::test]
+async fn upload_reports_the_instance_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"status": 1, "message": "Please wait 10 seconds between each post."})))
+        .mount(&server)
+        .await;
+
+    let backend = PrivateBinBackend::new(reqwest::Client::new(), server.uri().parse().unwrap()).unwrap();
+    let err = backend.upload("hello", &UploadOptions::default()).await.unwrap_err();
+
+    assert!(matches!(err, PasteError::UploadRejected { ref reason, .. } if reason.contains("wait 10 seconds")));
+}
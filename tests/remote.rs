@@ -0,0 +1,44 @@
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zero_paste::{fetch_url, is_remote, PasteError};
+
+async fn serve(body: &str, content_type: &str) -> (MockServer, reqwest::Url) {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/snippet.rs"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body.as_bytes().to_vec(), content_type))
+        .mount(&server)
+        .await;
+    let url = format!("{}/snippet.rs", server.uri()).parse().unwrap();
+    (server, url)
+}
+
+#[test]
+fn recognizes_remote_files() {
+    assert!(is_remote("https://example.com/a.rs"));
+    assert!(is_remote("http://example.com"));
+    assert!(!is_remote("src/main.rs"));
+    assert!(!is_remote("-"));
+}
+
+#[tokio::test]
+async fn fetches_text() {
+    let (_server, url) = serve("fn main() {}\n", "text/plain; charset=utf-8").await;
+    let content = fetch_url(&reqwest::Client::new(), &url, Some(1024), false).await.unwrap();
+    assert_eq!(content, b"fn main() {}\n");
+}
+
+#[tokio::test]
+async fn refuses_binary_responses_for_text_backends() {
+    let (_server, url) = serve("\u{0}\u{1}", "application/octet-stream").await;
+    let err = fetch_url(&reqwest::Client::new(), &url, None, false).await.unwrap_err();
+    assert!(matches!(&err, PasteError::BinaryContent(mime) if mime == "application/octet-stream"), "{}", err);
+    assert!(fetch_url(&reqwest::Client::new(), &url, None, true).await.is_ok());
+}
+
+#[tokio::test]
+async fn refuses_responses_over_the_limit() {
+    let (_server, url) = serve(&"x".repeat(2048), "text/plain").await;
+    let err = fetch_url(&reqwest::Client::new(), &url, Some(1024), false).await.unwrap_err();
+    assert!(matches!(err, PasteError::RemoteTooLarge { limit: 1024, .. }));
+}
@@ -0,0 +1,242 @@
+use zero_paste::{add_lines, check_syntax, decode_base64, encode_base64, expand_tabs, first_line_title, minify, normalize_eol, prepare_content, slice_lines, strip_comments, trim_trailing, wrap_lines, FileOptions, HasteBackend, LineRange, PasteContent, PasteError};
+
+#[test]
+fn normalizes_line_endings() {
+    assert_eq!(normalize_eol("a\r\nb\rc\nd"), "a\nb\nc\nd");
+    assert_eq!(normalize_eol("\r\n\r\r\n"), "\n\n\n");
+    assert_eq!(normalize_eol("unchanged\n"), "unchanged\n");
+}
+
+#[test]
+fn trims_trailing_whitespace() {
+    assert_eq!(trim_trailing("a  \nb\t\n  c"), "a\nb\n  c");
+    assert_eq!(trim_trailing("crlf \t\r\nkept\r\n"), "crlf\r\nkept\r\n");
+    assert_eq!(trim_trailing("   \n"), "\n");
+    assert_eq!(trim_trailing(""), "");
+}
+
+#[test]
+fn parses_line_ranges() {
+    assert_eq!("40:80".parse(), Ok(LineRange { start: 40, end: Some(80) }));
+    assert_eq!("3:".parse(), Ok(LineRange { start: 3, end: None }));
+    for invalid in ["", "40", "0:3", "5:2", "a:b", "1:2:3"] {
+        assert!(invalid.parse::<LineRange>().is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn slices_lines() {
+    let content = b"one\ntwo\r\nthree\nfour";
+    let range = |s: &str| s.parse::<LineRange>().unwrap();
+    assert_eq!(slice_lines(content, range("2:3")).unwrap(), b"two\r\nthree\n");
+    assert_eq!(slice_lines(content, range("4:4")).unwrap(), b"four");
+    assert_eq!(slice_lines(content, range("3:")).unwrap(), b"three\nfour");
+    assert!(matches!(slice_lines(content, range("2:5")), Err(PasteError::LinesOutOfRange { lines: 4, .. })));
+    assert!(slice_lines(content, range("5:")).is_err());
+}
+
+#[test]
+fn adds_filename_header_below_shebang() {
+    let backend = HasteBackend::new(reqwest::Client::new(), "http://127.0.0.1:1".parse().unwrap()).unwrap();
+    let opts = FileOptions { with_filename: true, ..Default::default() };
+
+    let prepared = prepare_content(&backend, b"#!/bin/sh\necho hi\n".to_vec(), Some("run.sh"), &opts).unwrap();
+    let PasteContent::Bytes(content) = prepared.content else { panic!("expected bytes") };
+    assert_eq!(content, b"#!/bin/sh\n# run.sh\necho hi\n");
+
+    let prepared = prepare_content(&backend, b"fn main() {}\n".to_vec(), Some("main.rs"), &opts).unwrap();
+    let PasteContent::Bytes(content) = prepared.content else { panic!("expected bytes") };
+    assert_eq!(content, b"// main.rs\nfn main() {}\n");
+}
+
+#[test]
+fn default_lang_is_only_the_last_resort() {
+    let backend = HasteBackend::new(reqwest::Client::new(), "http://127.0.0.1:1".parse().unwrap()).unwrap();
+    let opts = FileOptions { default_lang: Some("python".to_string()), ..Default::default() };
+
+    let prepared = prepare_content(&backend, b"fn main() {}\n".to_vec(), Some("main.rs"), &opts).unwrap();
+    assert_eq!(prepared.opts.lang, "rust");
+    let prepared = prepare_content(&backend, b"some notes\n".to_vec(), None, &opts).unwrap();
+    assert_eq!(prepared.opts.lang, "python");
+}
+
+#[test]
+fn added_lines_leave_minifying_alone() {
+    let backend = HasteBackend::new(reqwest::Client::new(), "http://127.0.0.1:1".parse().unwrap()).unwrap();
+    let opts = FileOptions { minify: true, prepend: vec!["// config".to_string()], ..Default::default() };
+
+    let prepared = prepare_content(&backend, b"{ \"a\": 1 }\n".to_vec(), Some("config.json"), &opts).unwrap();
+    let PasteContent::Bytes(content) = prepared.content else { panic!("expected bytes") };
+    assert_eq!(content, b"// config\n{\"a\":1}\n");
+}
+
+#[test]
+fn base64_round_trips_binary_content() {
+    let content: Vec<u8> = (0..=255).cycle().take(1000).collect();
+    let encoded = encode_base64(&content);
+    assert!(encoded.lines().all(|line| line.len() <= 76));
+    assert_eq!(decode_base64(encoded.as_bytes()).unwrap(), content);
+    assert!(matches!(decode_base64(b"not base64!"), Err(PasteError::InvalidBase64(_))));
+}
+
+#[test]
+fn base64_lets_binary_onto_text_backends() {
+    let backend = HasteBackend::new(reqwest::Client::new(), "http://127.0.0.1:1".parse().unwrap()).unwrap();
+    let opts = FileOptions { base64: true, ..Default::default() };
+
+    let prepared = prepare_content(&backend, vec![0x89, b'P', b'N', b'G', 0xff], Some("image.png"), &opts).unwrap();
+    assert_eq!(prepared.opts.lang, "_text");
+    let PasteContent::Bytes(content) = prepared.content else { panic!("expected bytes") };
+    assert_eq!(content, b"iVBOR/8=\n");
+}
+
+#[test]
+fn wraps_long_lines_at_spaces() {
+    assert_eq!(wrap_lines("the quick brown fox\n", 10), "the quick\nbrown fox\n");
+    assert_eq!(wrap_lines("short\r\nlines stay\r\n", 10), "short\r\nlines stay\r\n");
+    assert_eq!(wrap_lines("abcdefghij klm", 10), "abcdefghij\nklm");
+    assert_eq!(wrap_lines("a very long line", 4), "a\nvery\nlong\nline");
+}
+
+#[test]
+fn breaks_words_without_spaces() {
+    assert_eq!(wrap_lines("0123456789abcdef\n", 6), "012345\n6789ab\ncdef\n");
+    assert_eq!(wrap_lines("ééééé", 2), "éé\néé\né");
+    assert_eq!(wrap_lines("", 5), "");
+}
+
+#[test]
+fn appends_to_buffer_on_a_new_line() {
+    let buffer = std::env::temp_dir().join(format!("zero-paste-buffer-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&buffer);
+
+    zero_paste::append_to_buffer(&buffer, b"first").unwrap();
+    zero_paste::append_to_buffer(&buffer, b"second\n").unwrap();
+    zero_paste::append_to_buffer(&buffer, b"").unwrap();
+    zero_paste::append_to_buffer(&buffer, b"third\n").unwrap();
+    assert_eq!(std::fs::read_to_string(&buffer).unwrap(), "first\nsecond\nthird\n");
+    std::fs::remove_file(&buffer).unwrap();
+}
+
+#[test]
+fn minifies_json_keeping_key_order_and_lines() {
+    let json = "{\n  \"b\": [1, 2],\n  \"a\": \"keep  this \\\" space\"\n}\n";
+    assert_eq!(minify("json", json).unwrap().unwrap(), "{\"b\":[1,2],\"a\":\"keep  this \\\" space\"}\n");
+    assert_eq!(minify("json", "{ \"a\": 1 }\n{ \"a\": 2 }\n").unwrap().unwrap(), "{\"a\":1}\n{\"a\":2}\n");
+    assert!(minify("json", "{\"a\": }").is_err());
+}
+
+#[test]
+fn minifies_css() {
+    let css = "/* header */\na :hover,\nb > i {\n  color: red;\n  content: \"a  b\";\n}\n";
+    assert_eq!(minify("css", css).unwrap().unwrap(), "a :hover,b > i{color:red;content:\"a  b\";}\n");
+    assert!(minify("css", "a { /* open").is_err());
+}
+
+#[test]
+fn minifies_js_by_whole_lines() {
+    let js = "// setup\nfunction f() {\n    const s = `line one\n    indented`;\n\n    return s // trailing\n}\n";
+    assert_eq!(minify("js", js).unwrap().unwrap(), "function f() {\nconst s = `line one\n    indented`;\nreturn s // trailing\n}\n");
+}
+
+#[test]
+fn leaves_other_languages_alone() {
+    assert_eq!(minify("python", "def f():\n    pass\n").unwrap(), None);
+}
+
+#[test]
+fn strips_c_family_comments_outside_strings() {
+    let c = "/* header\n * lines */\nint/**/x = 1; // one\nchar *s = \"// not /* a */ comment\";\nchar q = '\"'; // quote\n";
+    assert_eq!(strip_comments("c", c).unwrap(), "int x = 1;\nchar *s = \"// not /* a */ comment\";\nchar q = '\"';\n");
+    let js = "const re = /\\/\\/ and \"/; // re\nconst half = a / 2; // half\nconst t = `// kept`;\n";
+    assert_eq!(strip_comments("js", js).unwrap(), "const re = /\\/\\/ and \"/;\nconst half = a / 2;\nconst t = `// kept`;\n");
+}
+
+#[test]
+fn strips_rust_comments() {
+    let rust = "/// Docs\nfn f<'a>(s: &'a str) -> char { /* outer /* inner */ still */\n    let _ = r#\"// raw \"quote\" /*\"#; // raw\n    '\"' // char\n}\n";
+    assert_eq!(strip_comments("rust", rust).unwrap(), "fn f<'a>(s: &'a str) -> char {\n    let _ = r#\"// raw \"quote\" /*\"#;\n    '\"'\n}\n");
+}
+
+#[test]
+fn strips_hash_comments() {
+    let python = "#!/usr/bin/env python3\n# setup\nx = \"# kept\"  # set x\ns = '''\n# in a string\n'''\n";
+    assert_eq!(strip_comments("python", python).unwrap(), "#!/usr/bin/env python3\nx = \"# kept\"\ns = '''\n# in a string\n'''\n");
+    let bash = "echo ${#args} a#b '# kept' \\# # gone\ncat <<-EOF # here\n\t# in the heredoc\n\tEOF\n# last";
+    assert_eq!(strip_comments("bash", bash).unwrap(), "echo ${#args} a#b '# kept' \\#\ncat <<-EOF\n\t# in the heredoc\n\tEOF\n");
+}
+
+#[test]
+fn keeps_comments_in_unknown_languages() {
+    assert_eq!(strip_comments("sql", "-- query\nSELECT 1;\n"), None);
+
+    let opts = FileOptions { strip_comments: true, ..Default::default() };
+    let backend = HasteBackend::new(reqwest::Client::new(), "http://127.0.0.1:1".parse().unwrap()).unwrap();
+    let prepared = prepare_content(&backend, b"x = 1  # one\n".to_vec(), Some("a.py"), &opts).unwrap();
+    let PasteContent::Bytes(content) = prepared.content else { panic!("expected bytes") };
+    assert_eq!(content, b"x = 1\n");
+}
+
+#[test]
+fn adds_lines_around_content_in_order() {
+    let before = ["Repro for bug #123".to_string(), "run with --release".to_string()];
+    let after = ["end of log".to_string()];
+    assert_eq!(add_lines(b"panic!\n", &before, &after), b"Repro for bug #123\nrun with --release\npanic!\nend of log\n");
+    assert_eq!(add_lines(b"no newline", &[], &after), b"no newline\nend of log\n");
+    assert_eq!(add_lines(b"", &before[..1], &[]), b"Repro for bug #123\n");
+}
+
+#[test]
+fn checks_syntax_of_config_formats() {
+    assert!(check_syntax("json", None, "{\"a\": [1, 2]}\n{\"b\": null}\n").is_ok());
+    let err = check_syntax("json", Some("data.json"), "{\n  \"a\": 1,\n}\n").unwrap_err();
+    assert!(matches!(&err, PasteError::InvalidSyntax { format: "JSON", reason } if reason.contains("line 3 column 1")), "{}", err);
+
+    assert!(check_syntax("yaml", None, "a: 1\n---\nb: [2]\n").is_ok());
+    let err = check_syntax("yaml", None, "a: 1\n  b: 2\n").unwrap_err();
+    assert!(matches!(&err, PasteError::InvalidSyntax { format: "YAML", reason } if reason.contains("line 2")), "{}", err);
+
+    assert!(check_syntax("ini", Some("Cargo.toml"), "[package]\nname = \"x\"\n").is_ok());
+    let err = check_syntax("ini", Some("Cargo.toml"), "[package]\nname = \n").unwrap_err();
+    assert!(matches!(&err, PasteError::InvalidSyntax { format: "TOML", reason } if reason.contains("line 2 column 8")), "{}", err);
+    // Only files named .toml are taken for TOML, ini files are left alone
+    assert!(check_syntax("ini", Some("setup.ini"), "name = \n").is_ok());
+    assert!(check_syntax("python", None, "def (:\n").is_ok());
+}
+
+#[test]
+fn expands_tabs_to_tab_stops() {
+    assert_eq!(expand_tabs("\tif x:\n\t\treturn\n", 4), "    if x:\n        return\n");
+    assert_eq!(expand_tabs("ab\tc\r\nabcd\te", 4), "ab  c\r\nabcd    e");
+    assert_eq!(expand_tabs("a\tb", 8), "a       b");
+}
+
+#[test]
+fn unreadable_input_names_the_file() {
+    let backend = HasteBackend::new(reqwest::Client::new(), "http://127.0.0.1:1".parse().unwrap()).unwrap();
+    let err = zero_paste::prepare_file(&backend, "no/such/file.rs", &FileOptions::default()).unwrap_err();
+    assert!(matches!(&err, PasteError::ReadInput { path, source } if path.as_os_str() == "no/such/file.rs" && source.kind() == std::io::ErrorKind::NotFound));
+    assert_eq!(err.to_string(), "cannot read 'no/such/file.rs': no such file or directory");
+}
+
+#[test]
+fn title_comes_from_the_first_line_with_text() {
+    assert_eq!(first_line_title("\n## Release notes ##\n\nDetails").as_deref(), Some("Release notes"));
+    assert_eq!(first_line_title("#!/bin/sh\n# Backs up the database\n").as_deref(), Some("Backs up the database"));
+    assert_eq!(first_line_title("/*\n * Parser for C# files */\n").as_deref(), Some("Parser for C# files"));
+    assert_eq!(first_line_title("<!-- About -->\n").as_deref(), Some("About"));
+    assert_eq!(first_line_title(&"x".repeat(200)).map(|title| title.len()), Some(80));
+    assert_eq!(first_line_title("\n#\n  \n"), None);
+}
+
+#[test]
+fn explicit_title_wins_over_the_first_line() {
+    let backend = HasteBackend::new(reqwest::Client::new(), "http://127.0.0.1:1".parse().unwrap()).unwrap();
+    let opts = FileOptions { title_from_first_line: true, ..Default::default() };
+    let prepared = prepare_content(&backend, b"# Notes\ntext\n".to_vec(), Some("notes.md"), &opts).unwrap();
+    assert_eq!(prepared.opts.title, "Notes");
+
+    let opts = FileOptions { title: Some("Mine".to_string()), ..opts };
+    let prepared = prepare_content(&backend, b"# Notes\ntext\n".to_vec(), Some("notes.md"), &opts).unwrap();
+    assert_eq!(prepared.opts.title, "Mine");
+}
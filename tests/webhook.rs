@@ -0,0 +1,43 @@
+use serde_json::json;
+use wiremock::matchers::{body_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zero_paste::{PasteBackend, PasteError, UploadOptions, WebhookBackend};
+
+#[tokio::test]
+async fn posts_json_and_reads_url_at_pointer() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/hook"))
+        .and(body_json(json!({"content": "hello", "lang": "rust", "filename": "main.rs"})))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({"data": {"link": "https://pastes.example/1"}})))
+        .mount(&server)
+        .await;
+
+    let url = format!("{}/hook", server.uri()).parse().unwrap();
+    let backend = WebhookBackend::new(reqwest::Client::new(), url, Some("/data/link".to_string())).unwrap();
+    let opts = UploadOptions { lang: "rust".to_string(), filename: Some("main.rs".to_string()), ..UploadOptions::default() };
+    let uploaded = backend.upload("hello", &opts).await.unwrap();
+
+    assert_eq!(uploaded.url.as_str(), "https://pastes.example/1");
+}
+
+#[tokio::test]
+async fn response_without_url_is_an_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+        .mount(&server)
+        .await;
+
+    let backend = WebhookBackend::new(reqwest::Client::new(), server.uri().parse().unwrap(), None).unwrap();
+    let err = backend.upload("hello", &UploadOptions::default()).await.unwrap_err();
+    assert!(matches!(err, PasteError::InvalidResponse(body) if body == "ok"));
+
+    server.reset().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": 1})))
+        .mount(&server)
+        .await;
+    let err = backend.upload("hello", &UploadOptions::default()).await.unwrap_err();
+    assert!(matches!(err, PasteError::InvalidResponse(reason) if reason.starts_with("no URL at /url")));
+}